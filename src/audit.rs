@@ -0,0 +1,287 @@
+use std::{
+    fmt::{self, Display},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::config::{self, Settings};
+
+const LOG_FILE: &str = "audit.jsonl";
+// Past this size the log is moved aside to audit.1.jsonl, older files shift up and the oldest is dropped
+const MAX_BYTES: u64 = 1024 * 1024;
+const ROTATED: usize = 3;
+
+// Who turned the limiter on or off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cause {
+    User,
+    AutoLimiter,
+    Schedule,
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::User => write!(f, "user"),
+            Cause::AutoLimiter => write!(f, "auto limiter"),
+            Cause::Schedule => write!(f, "schedule"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    // The engine moved the volume from `before` to `after`
+    Clamp { before: u8, after: u8, device: String },
+    Limiter {
+        enabled: bool,
+        cause: Cause,
+        // Schedule rule that caused it, if any
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rule: Option<String>,
+    },
+    Setting { name: String, value: String },
+    Override { percent: u8, minutes: u8 },
+    OverrideEnded,
+    Escalation { attempts: u8, window_minutes: u8 },
+}
+
+impl Event {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Event::Clamp { .. } => Kind::Clamp,
+            Event::Limiter { .. } => Kind::Limiter,
+            Event::Setting { .. } => Kind::Setting,
+            Event::Override { .. } | Event::OverrideEnded => Kind::Override,
+            Event::Escalation { .. } => Kind::Tamper,
+        }
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Clamp { before, after, device } => write!(f, "Volume {}% -> {}% on {}", before, after, device),
+            Event::Limiter { enabled, cause, rule } => {
+                write!(f, "Limiter {} by {}", if *enabled {"enabled"} else {"disabled"}, cause)?;
+                match rule {
+                    Some(rule) => write!(f, " ({})", rule),
+                    None => Ok(()),
+                }
+            },
+            Event::Setting { name, value } => write!(f, "{} set to {}", name, value),
+            Event::Override { percent, minutes } => write!(f, "Limit raised to {}% for {} minutes", percent, minutes),
+            Event::OverrideEnded => write!(f, "Temporary limit ended"),
+            Event::Escalation { attempts, window_minutes } => write!(f, "{} attempts past the limit within {} minutes", attempts, window_minutes),
+        }
+    }
+}
+
+// Groups of events the viewer filters by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Clamp,
+    Limiter,
+    Setting,
+    Override,
+    Tamper,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] = [Kind::Clamp, Kind::Limiter, Kind::Setting, Kind::Override, Kind::Tamper];
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Clamp => write!(f, "Clamps"),
+            Kind::Limiter => write!(f, "Limiter"),
+            Kind::Setting => write!(f, "Settings"),
+            Kind::Override => write!(f, "Overrides"),
+            Kind::Tamper => write!(f, "Tampering"),
+        }
+    }
+}
+
+// One line of the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: NaiveDateTime,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+fn path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(if index == 0 {LOG_FILE.to_owned()} else {format!("audit.{}.jsonl", index)})
+}
+
+fn rotate(dir: &Path) -> io::Result<()> {
+    for index in (0..ROTATED).rev() {
+        let from = path(dir, index);
+        if from.exists() {
+            fs::rename(from, path(dir, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn append(entry: &Entry) -> io::Result<()> {
+    let dir = config::state_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+    append_in(&dir, entry)
+}
+
+fn append_in(dir: &Path, entry: &Entry) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = path(dir, 0);
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= MAX_BYTES) {
+        rotate(dir)?;
+    }
+    let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+// Logging never gets in the way of limiting, failures only go to stderr
+pub fn record(time: NaiveDateTime, event: Event) {
+    if let Err(error) = append(&Entry { time, event }) {
+        eprintln!("Failed to write audit log: {}", error);
+    }
+}
+
+// Up to `limit` of the latest entries, oldest first, reaching into rotated files when the current one is short
+pub fn read_recent(limit: usize) -> Vec<Entry> {
+    config::state_dir().map_or_else(Vec::new, |dir| read_recent_in(&dir, limit))
+}
+
+fn read_recent_in(dir: &Path, limit: usize) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for index in 0..=ROTATED {
+        let Ok(contents) = fs::read_to_string(path(dir, index)) else {
+            break;
+        };
+        let mut older: Vec<Entry> = contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+        older.append(&mut entries);
+        entries = older;
+        if entries.len() >= limit {
+            break;
+        }
+    }
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
+
+// Every setting that differs between two snapshots, nested ones by their dotted path
+pub fn settings_changes(old: &Settings, new: &Settings) -> Vec<Event> {
+    let (Ok(old), Ok(new)) = (Value::try_from(old), Value::try_from(new)) else {
+        return Vec::new();
+    };
+    let mut changes = Vec::new();
+    diff("", &old, &new, &mut changes);
+    changes
+}
+
+fn diff(name: &str, old: &Value, new: &Value, changes: &mut Vec<Event>) {
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            let keys = old.keys().chain(new.keys().filter(|key| !old.contains_key(*key)));
+            for key in keys {
+                let name = if name.is_empty() {key.clone()} else {format!("{}.{}", name, key)};
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff(&name, old, new, changes),
+                    (_, new) => push_change(name, new, changes),
+                }
+            }
+        },
+        _ if old != new => push_change(name.to_owned(), Some(new), changes),
+        _ => {},
+    }
+}
+
+fn push_change(name: String, value: Option<&Value>, changes: &mut Vec<Event>) {
+    // The PIN hash stays out of the log, only the fact it changed goes in
+    let value = match value {
+        Some(_) if name == "pin_hash" => String::from("(changed)"),
+        Some(value) => value.to_string(),
+        None => String::from("(removed)"),
+    };
+    changes.push(Event::Setting { name, value });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{at, temp_path};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = temp_path(name);
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn entry(minute: u32) -> Entry {
+        Entry { time: at(14, 20, minute), event: Event::OverrideEnded }
+    }
+
+    // Pads the current log to the rotation size, with lines the reader skips
+    fn fill(dir: &Path) {
+        let mut file = OpenOptions::new().append(true).open(path(dir, 0)).unwrap();
+        file.write_all(&vec![b'x'; MAX_BYTES as usize]).unwrap();
+    }
+
+    #[test]
+    fn full_logs_rotate_and_the_oldest_is_dropped() {
+        let dir = temp_dir("audit-rotate");
+        append_in(&dir, &entry(0)).unwrap();
+        append_in(&dir, &entry(1)).unwrap();
+        assert!(!path(&dir, 1).exists());
+        for minute in 2..=ROTATED as u32 + 2 {
+            fill(&dir);
+            append_in(&dir, &entry(minute)).unwrap();
+        }
+        assert!(path(&dir, ROTATED).exists());
+        assert!(!path(&dir, ROTATED + 1).exists());
+        // The file holding the first two entries went past the last rotated slot
+        assert_eq!(read_recent_in(&dir, usize::MAX), (2..=ROTATED as u32 + 2).map(entry).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recent_entries_reach_into_rotated_files_oldest_first() {
+        let dir = temp_dir("audit-recent");
+        for minute in 0..3 {
+            append_in(&dir, &entry(minute)).unwrap();
+        }
+        rotate(&dir).unwrap();
+        for minute in 3..5 {
+            append_in(&dir, &entry(minute)).unwrap();
+        }
+        assert_eq!(read_recent_in(&dir, 2), vec![entry(3), entry(4)]);
+        assert_eq!(read_recent_in(&dir, 3), vec![entry(2), entry(3), entry(4)]);
+        assert_eq!(read_recent_in(&dir, usize::MAX), (0..5).map(entry).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pin_hash_changes_are_logged_without_the_hash() {
+        let changes = |old: &str, new: &str| {
+            let mut changes = Vec::new();
+            diff("", &toml::from_str(old).unwrap(), &toml::from_str(new).unwrap(), &mut changes);
+            changes
+        };
+        let setting = |value: &str| Event::Setting { name: String::from("pin_hash"), value: value.to_owned() };
+        assert_eq!(changes("", "pin_hash = \"$argon2id$secret\""), vec![setting("(changed)")]);
+        assert_eq!(changes("pin_hash = \"a\"", "pin_hash = \"b\""), vec![setting("(changed)")]);
+        assert_eq!(changes("pin_hash = \"a\"", ""), vec![setting("(removed)")]);
+        // Settings never carry it into the log in the first place
+        let new = Settings { pin_hash: Some(String::from("$argon2id$secret")), ..Settings::default() };
+        assert!(settings_changes(&Settings::default(), &new).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    io::{self, BufRead, IsTerminal},
+    path::PathBuf,
+};
+
+use crate::{
+    audit,
+    export::{Export, Format, Range},
+    ipc::{self, Proof, Request},
+    pin,
+    vol_ctl::{history::History, schedule::{Clock, SystemClock}, sleep_timer::SleepSettings},
+};
+
+pub const USAGE: &str = "\
+Usage: vol-limiter [COMMAND]
+
+Without a command the app window opens. While the settings are locked, commands
+that loosen the limits ask for the PIN, or read it from the first line of
+standard input when that isn't a terminal.
+
+Requests are carried out by the running app the next time it polls, which can
+still refuse them.
+
+Commands:
+  sleep <MINUTES> [--fade <MINUTES>] [--floor <PERCENT>] [--then nothing|mute|pause]
+      Fade the volume out and stop in MINUTES
+  sleep cancel
+      Cancel a running sleep timer
+  lock
+      Lock the settings again after they were unlocked in the app
+  override <PERCENT> <MINUTES>
+      Set the limit to PERCENT for MINUTES, then go back to the usual one
+  override cancel
+      End a temporary limit early (needs the PIN while locked)
+  export csv|json [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--out <DIR>]
+      Write volume samples, clamps and per-device usage to DIR (default: the
+      current directory), covering the last week unless a range is given
+  help
+      Show this message";
+
+// Runs a command against the running app, returning what to print
+pub fn run(args: &[String]) -> Result<String, String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["help" | "--help" | "-h"] => Ok(USAGE.to_owned()),
+        ["sleep", "cancel"] => {
+            send(Request::CancelSleep)?;
+            Ok(String::from("Requested cancelling the sleep timer"))
+        },
+        ["override", "cancel"] => {
+            send(Request::CancelOverride)?;
+            Ok(String::from("Requested ending the temporary limit"))
+        },
+        ["override", percent, minutes] => {
+            let (percent, minutes) = (number(percent)?.min(100), number(minutes)?);
+            send(Request::Override(percent, minutes))?;
+            Ok(format!("Requested a limit of {}% for {} minutes", percent, minutes))
+        },
+        ["lock"] => {
+            send(Request::Lock)?;
+            Ok(String::from("Requested locking the settings"))
+        },
+        ["export", format, options @ ..] => export(format.parse()?, options),
+        ["sleep", minutes, options @ ..] => {
+            let mut sleep = SleepSettings {
+                minutes: number(minutes)?,
+                ..SleepSettings::default()
+            };
+            let mut options = options.iter();
+            while let Some(option) = options.next() {
+                let value = options.next().ok_or_else(|| format!("missing value for {}", option))?;
+                match *option {
+                    "--fade" => sleep.fade_minutes = number(value)?,
+                    "--floor" => sleep.floor = number(value)?.min(100),
+                    "--then" => sleep.after = value.parse()?,
+                    _ => return Err(format!("unknown option {}\n\n{}", option, USAGE)),
+                }
+            }
+            send(Request::Sleep(sleep))?;
+            Ok(format!("Requested sleeping in {} minutes", sleep.minutes))
+        },
+        _ => Err(format!("unknown command\n\n{}", USAGE)),
+    }
+}
+
+// Reads the history and audit log straight from disk, the app doesn't need to be running
+fn export(format: Format, options: &[&str]) -> Result<String, String> {
+    let now = SystemClock.now();
+    let mut range = Range::last_week(now.date());
+    let mut dir = PathBuf::from(".");
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| format!("missing value for {}", option))?;
+        match *option {
+            "--from" => range.from = Range::parse_date(value)?,
+            "--to" => range.to = Range::parse_date(value)?,
+            "--out" => dir = PathBuf::from(value),
+            _ => return Err(format!("unknown option {}\n\n{}", option, USAGE)),
+        }
+    }
+    let range = Range::new(range.from, range.to)?;
+    let files = Export::collect(range, &History::load(now), &audit::read_recent(usize::MAX))
+        .write(&dir, format)
+        .map_err(|error| format!("failed to export: {}", error))?;
+    Ok(files.iter().map(|path| format!("Wrote {}", path.display())).collect::<Vec<_>>().join("\n"))
+}
+
+fn number(value: &str) -> Result<u8, String> {
+    value.parse().map_err(|error| format!("invalid number \"{}\": {}", value, error))
+}
+
+// Only a proof signed with the stored hash is sent along, the app checks it again
+fn send(request: Request) -> Result<(), String> {
+    let proof = match pin::load_hash() {
+        Some(hash) if request.is_protected() => {
+            let pin = read_pin()?;
+            if !pin::verify(&hash, &pin) {
+                return Err(String::from("wrong PIN"));
+            }
+            Some(Proof::sign(request, &hash))
+        },
+        _ => None,
+    };
+    ipc::send(request, proof.as_ref()).map_err(|error| format!("failed to reach the app: {}", error))
+}
+
+// Never taken as an argument, where anyone listing processes could read it
+fn read_pin() -> Result<String, String> {
+    let pin = if io::stdin().is_terminal() {
+        rpassword::prompt_password("The settings are locked, enter the PIN: ")
+    } else {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map(|_| line.trim_end_matches(['\r', '\n']).to_owned())
+    };
+    pin.map_err(|error| format!("failed to read the PIN: {}", error))
+}
@@ -0,0 +1,90 @@
+use iced::{
+    mouse,
+    widget::canvas::{self, Frame, Geometry, LineDash, Path, Stroke, Text},
+    Pixels, Point, Rectangle, Renderer, Size, Theme,
+};
+
+use crate::vol_ctl::history::Bucket;
+
+// Room on the left for the percent labels
+const LABEL_WIDTH: f32 = 36.0;
+const MARGIN: f32 = 8.0;
+
+// Canvas program plotting volume, the ceiling and clamps, one bucket per slice of the span
+pub struct HistoryGraph {
+    buckets: Vec<Bucket>,
+}
+
+impl HistoryGraph {
+    pub fn new(buckets: Vec<Bucket>) -> Self {
+        Self { buckets }
+    }
+}
+
+impl<Message> canvas::Program<Message> for HistoryGraph {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let palette = theme.extended_palette();
+        let mut frame = Frame::new(renderer, bounds.size());
+        let plot = Rectangle::new(
+            Point::new(LABEL_WIDTH, MARGIN),
+            Size::new((bounds.width - LABEL_WIDTH - MARGIN).max(1.0), (bounds.height - 2.0 * MARGIN).max(1.0)),
+        );
+        let y = |percent: u8| plot.y + plot.height * (1.0 - percent.min(100) as f32 / 100.0);
+        let step = plot.width / self.buckets.len().max(1) as f32;
+        let x = |index: usize| plot.x + step * (index as f32 + 0.5);
+
+        for percent in [0, 25, 50, 75, 100] {
+            frame.stroke(
+                &Path::line(Point::new(plot.x, y(percent)), Point::new(plot.x + plot.width, y(percent))),
+                Stroke::default().with_color(palette.background.strong.color).with_width(1.0),
+            );
+            frame.fill_text(Text {
+                content: format!("{}%", percent),
+                position: Point::new(0.0, y(percent) - 6.0),
+                color: palette.background.base.text,
+                size: Pixels(11.0),
+                ..Text::default()
+            });
+        }
+
+        // Clamps rise from the bottom, the busiest slice reaching a quarter of the height
+        let most = self.buckets.iter().map(|bucket| bucket.clamps).max().unwrap_or(0).max(1);
+        for (index, bucket) in self.buckets.iter().enumerate().filter(|(_, bucket)| bucket.clamps > 0) {
+            let height = plot.height * 0.25 * bucket.clamps as f32 / most as f32;
+            frame.fill_rectangle(
+                Point::new(x(index) - step.min(4.0) / 2.0, plot.y + plot.height - height),
+                Size::new(step.min(4.0), height),
+                palette.danger.base.color,
+            );
+        }
+
+        let ceiling = polyline(self.buckets.iter().enumerate().map(|(index, bucket)| bucket.ceiling.map(|ceiling| Point::new(x(index), y(ceiling)))));
+        frame.stroke(&ceiling, Stroke {
+            line_dash: LineDash { segments: &[6.0, 4.0], offset: 0 },
+            ..Stroke::default().with_color(palette.secondary.base.color).with_width(2.0)
+        });
+        let volume = polyline(self.buckets.iter().enumerate().map(|(index, bucket)| bucket.volume.map(|volume| Point::new(x(index), y(volume)))));
+        frame.stroke(&volume, Stroke::default().with_color(palette.primary.base.color).with_width(2.0));
+
+        vec![frame.into_geometry()]
+    }
+}
+
+// Connects consecutive points, leaving a gap wherever there is no data
+fn polyline(points: impl Iterator<Item = Option<Point>>) -> Path {
+    Path::new(|builder| {
+        let mut drawing = false;
+        for point in points {
+            match point {
+                Some(point) if drawing => builder.line_to(point),
+                Some(point) => {
+                    builder.move_to(point);
+                    drawing = true;
+                },
+                None => drawing = false,
+            }
+        }
+    })
+}
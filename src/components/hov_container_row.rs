@@ -1,15 +1,19 @@
+use std::{cell::Cell, time::Duration};
+
 use iced_core::{
     self, 
-    border::Border, 
+    border::{Border, Radius}, 
     layout::{self, Layout, Limits, Node}, 
     mouse::{self, Cursor}, 
-    widget::{tree::{self, Tree}, Operation}, 
+    time::Instant,
+    keyboard::{self, key},
+    widget::{operation::Focusable, tree::{self, Tree}, Id, Operation}, 
     Alignment, Background, Clipboard, Color, Element, Length, 
-    Padding, Rectangle, Shadow, Shell, Size, Theme, Widget, event, 
-    Vector, renderer, overlay,  
+    Padding, Pixels, Rectangle, Shadow, Shell, Size, Theme, Widget, event, 
+    Vector, renderer, overlay, window,
 };
 
-use crate::styles::{equal_radius, get_rgb_color};
+use crate::styles::{equal_radius, mix_color};
 
 pub struct HovContainer<'a, Message, Theme = iced::Theme, Renderer = iced::Renderer,> 
     where 
@@ -17,12 +21,21 @@ pub struct HovContainer<'a, Message, Theme = iced::Theme, Renderer = iced::Rende
         Theme: Catalog,
     {
     content: Vec<Element<'a, Message, Theme, Renderer>>,
+    axis: Axis,
+    spacing: f32,
+    align: Alignment,
     padding: Padding,
     height: Length,
     width: Length,
     on_hover: Option<OnHover<'a, Message>>,
     on_exit: Option<OnExit<'a, Message>>,
+    on_press: Option<OnPress<'a, Message>>,
+    on_release: Option<OnRelease<'a, Message>>,
+    focusable: bool,
+    id: Option<Id>,
     hover_col: Option<iced::Color>,
+    transition: Duration,
+    easing: Easing,
     theme: Theme::Class<'a>
 }
 
@@ -32,28 +45,27 @@ impl<'a, Message, Theme, Renderer> HovContainer<'a, Message, Theme, Renderer>
         Theme: Catalog,
         {
         pub fn new() -> Self{
-            let content = Vec::new();
-            Self {
-                content,
-                padding: DEFAULT_PADDING,
-                height: Length::Shrink,
-                width: Length::Shrink,
-                on_hover: None,
-                on_exit: None,
-                hover_col: None,  
-                theme: Theme::default(), 
-            }
+            Self::with_content(Vec::new())
         }
 
         pub fn with_content(content: Vec<Element<'a, Message, Theme, Renderer >>) -> Self{
             Self {
                 content,
+                axis: Axis::default(),
+                spacing: DEFAULT_SPACING,
+                align: Alignment::Center,
                 padding: DEFAULT_PADDING,
                 height: Length::Shrink,
                 width: Length::Shrink,
                 on_hover: None,
                 on_exit: None,
+                on_press: None,
+                on_release: None,
+                focusable: false,
+                id: None,
                 hover_col: None,  
+                transition: DEFAULT_TRANSITION,
+                easing: Easing::default(),
                 theme: Theme::default(), 
             }
         }
@@ -78,16 +90,72 @@ impl<'a, Message, Theme, Renderer> HovContainer<'a, Message, Theme, Renderer>
             self
         }
 
+        pub fn axis(mut self, axis: Axis) -> Self {
+            self.axis = axis;
+            self
+        }
+
+        pub fn spacing(mut self, spacing: impl Into<Pixels>) -> Self {
+            self.spacing = spacing.into().0;
+            self
+        }
+
+        pub fn align_items(mut self, align: impl Into<Alignment>) -> Self {
+            self.align = align.into();
+            self
+        }
+
         pub fn on_hover(mut self, message: Message) -> Self{
             self.on_hover = Some(OnHover::Direct(message));
             self
         } 
 
+        pub fn on_hover_with(mut self, on_hover: impl Fn() -> Message + 'a) -> Self {
+            self.on_hover = Some(OnHover::Closure(Box::new(on_hover)));
+            self
+        }
+
         pub fn on_exit(mut self, message: Message) -> Self {
             self.on_exit = Some(OnExit::Direct(message));
             self
         }
 
+        pub fn on_exit_with(mut self, on_exit: impl Fn() -> Message + 'a) -> Self {
+            self.on_exit = Some(OnExit::Closure(Box::new(on_exit)));
+            self
+        }
+
+        pub fn on_press(mut self, message: Message) -> Self {
+            self.on_press = Some(OnPress::Direct(message));
+            self
+        }
+
+        pub fn on_press_with(mut self, on_press: impl Fn() -> Message + 'a) -> Self {
+            self.on_press = Some(OnPress::Closure(Box::new(on_press)));
+            self
+        }
+
+        pub fn on_release(mut self, message: Message) -> Self {
+            self.on_release = Some(OnRelease::Direct(message));
+            self
+        }
+
+        pub fn on_release_with(mut self, on_release: impl Fn() -> Message + 'a) -> Self {
+            self.on_release = Some(OnRelease::Closure(Box::new(on_release)));
+            self
+        }
+
+        // Lets the container take keyboard focus without reacting to presses
+        pub fn focusable(mut self, focusable: bool) -> Self {
+            self.focusable = focusable;
+            self
+        }
+
+        pub fn id(mut self, id: impl Into<Id>) -> Self {
+            self.id = Some(id.into());
+            self
+        }
+
         pub fn padding<P>(mut self, padding: P) -> Self 
             where
                 P: Into<Padding> {
@@ -100,6 +168,16 @@ impl<'a, Message, Theme, Renderer> HovContainer<'a, Message, Theme, Renderer>
             self
         }
 
+        pub fn transition(mut self, duration: Duration) -> Self {
+            self.transition = duration;
+            self
+        }
+
+        pub fn easing(mut self, easing: Easing) -> Self {
+            self.easing = easing;
+            self
+        }
+
         pub fn push(
                 mut self,
                 child: impl Into<Element<'a, Message, Theme, Renderer>>,
@@ -127,9 +205,59 @@ impl<'a, Message, Theme, Renderer> HovContainer<'a, Message, Theme, Renderer>
             self.theme = (Box::new(style) as StyleFn<'a, Theme>).into();
             self
         }
-        
-        
 
+        fn is_pressable(&self) -> bool {
+            self.on_press.is_some() || self.on_release.is_some()
+        }
+
+        fn is_focusable(&self) -> bool {
+            self.focusable || self.is_pressable()
+        }
+
+        fn status(&self, state: &State) -> Status {
+            if self.on_hover.is_none() && !self.is_pressable() {
+                Status::Disabled
+            } else if state.is_pressed {
+                Status::Pressed
+            } else if state.is_focused {
+                Status::Focused
+            } else if state.is_hovered {
+                Status::Hovered
+            } else {
+                Status::NotHovered
+            }
+        }
+}
+
+impl<'a, Message, Theme, Renderer> HovContainer<'a, Message, Theme, Renderer>
+    where 
+        Message: Clone,
+        Renderer: iced_core::renderer::Renderer,
+        Theme: Catalog,
+        {
+        fn press(&self, state: &mut State, shell: &mut Shell<'_, Message>) {
+            state.is_pressed = true;
+            if let Some(message) = self.on_press.as_ref().map(OnPress::get) {
+                shell.publish(message);
+            }
+        }
+
+        fn release(&self, state: &mut State, shell: &mut Shell<'_, Message>) {
+            state.is_pressed = false;
+            if let Some(message) = self.on_release.as_ref().map(OnRelease::get) {
+                shell.publish(message);
+            }
+        }
+}
+
+impl<'a, Message, Theme, Renderer> Default for HovContainer<'a, Message, Theme, Renderer>
+    where
+        Renderer: iced_core::renderer::Renderer,
+        Theme: Catalog,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 enum OnHover <'a, Message> {
@@ -142,6 +270,16 @@ enum OnExit <'a, Message> {
     Closure(Box<dyn Fn() -> Message + 'a>),
 }
 
+enum OnPress <'a, Message> {
+    Direct(Message),
+    Closure(Box<dyn Fn() -> Message + 'a>),
+}
+
+enum OnRelease <'a, Message> {
+    Direct(Message),
+    Closure(Box<dyn Fn() -> Message + 'a>),
+}
+
 impl <'a, Message: Clone> OnHover <'a, Message> {
     fn get(&self) -> Message {
         match self {
@@ -160,9 +298,53 @@ impl <'a, Message: Clone> OnExit <'a, Message> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+impl <'a, Message: Clone> OnPress <'a, Message> {
+    fn get(&self) -> Message {
+        match self {
+            OnPress::Direct(message) => message.clone(),
+            OnPress::Closure(f) => f(),
+        }
+    }
+}
+
+impl <'a, Message: Clone> OnRelease <'a, Message> {
+    fn get(&self) -> Message {
+        match self {
+            OnRelease::Direct(message) => message.clone(),
+            OnRelease::Closure(f) => f(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
 struct State {
     is_hovered: bool,
+    is_pressed: bool,
+    is_focused: bool,
+    status: Option<Status>,
+    animation: Option<Animation>,
+    // Last style handed to the renderer, so an interrupted transition resumes from what is on screen
+    drawn: Cell<Option<Style>>,
+}
+
+impl Focusable for State {
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+
+    fn focus(&mut self) {
+        self.is_focused = true;
+    }
+
+    fn unfocus(&mut self) {
+        self.is_focused = false;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Animation {
+    from: Option<Style>,
+    started: Instant,
 }
 
 impl <'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> 
@@ -197,21 +379,25 @@ where
 
     fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &Limits) -> Node {
         layout::flex::resolve(
-            layout::flex::Axis::Horizontal,
+            self.axis.into(),
             renderer,
             limits,
             self.width,
             self.height,
             self.padding,
-            2.0,
-            Alignment::Center,
+            self.spacing,
+            self.align,
             &self.content,
             &mut tree.children,
         )
     }
 
     fn operate(&self, tree: &mut Tree, layout: Layout<'_>, renderer: &Renderer, operation: &mut dyn Operation) {
-        operation.container(None, layout.bounds(), &mut |operation| {
+        // Only focusable containers and those that react to presses take part in Tab navigation
+        if self.is_focusable() {
+            operation.focusable(tree.state.downcast_mut::<State>(), self.id.as_ref());
+        }
+        operation.container(self.id.as_ref(), layout.bounds(), &mut |operation| {
             self.content.iter().zip(&mut tree.children).zip(layout.children())
             .for_each(|((child, state), layout)| {
                 child.as_widget().operate(state, layout, renderer, operation);
@@ -220,113 +406,133 @@ where
     }
 
     fn on_event(&mut self, tree: &mut Tree, event: event::Event, layout: Layout<'_>, cursor: mouse::Cursor, renderer: &Renderer, clipboard: &mut dyn Clipboard, shell: &mut Shell<'_, Message>, viewport: &Rectangle,) -> event::Status {
-        
-        // self.content.iter_mut()
-        self.content
-        .iter_mut()
-        .zip(&mut tree.children)
-        .zip(layout.children())
-        .map(|((child, state), layout)| {
-            child.as_widget_mut().on_event(
-                state,
-                event.clone(),
-                layout,
-                cursor,
-                renderer,
-                clipboard,
-                shell,
-                viewport,
-            )
-        })
-        .fold(event::Status::Ignored, event::Status::merge);
+        let status = self.content
+            .iter_mut()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .map(|((child, state), layout)| {
+                child.as_widget_mut().on_event(
+                    state,
+                    event.clone(),
+                    layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    shell,
+                    viewport,
+                )
+            })
+            .fold(event::Status::Ignored, event::Status::merge);
 
-        if let Some(on_exit) = self.on_exit.as_ref().map(OnExit::get) {
-            if let Some(on_hover) = self.on_hover.as_ref().map(OnHover::get)
-            {
-                match tree.state {
-                    tree::State::None => {
-                        // eprintln!("State Empty");
-                        return event::Status::Ignored;
-                    }
-                    tree::State::Some(_) => {
-                        let state = tree.state.downcast_mut::<State>();
-                        // eprintln!("Something");
-                        let was_hovered = state.is_hovered;
-                        let mut now_hovered = false;
-                        if let Some(position) = cursor.position() {
-                            now_hovered = layout.bounds().contains(position);
-                        }
-
-                        match (was_hovered, now_hovered) {
-                            (true, false) => {
-                                state.is_hovered = now_hovered;
-                                shell.publish(on_exit);
-                                return event::Status::Captured;
-                            }
-                            (false, true) => {
-                                state.is_hovered = now_hovered;
-                                shell.publish(on_hover);
-                                return event::Status::Captured;
-                            }
-                            _ => {
-                                return event::Status::Ignored;
-                            }
-                        }
-                    }
-                }
+        let state = tree.state.downcast_mut::<State>();
+
+        if let event::Event::Window(window::Event::RedrawRequested(now)) = event
+            && let Some(animation) = state.animation
+        {
+            if now.saturating_duration_since(animation.started) >= self.transition {
+                state.animation = None;
+            } else {
+                shell.request_redraw(window::RedrawRequest::NextFrame);
             }
-            else {
-                event::Status::Ignored
+        }
+
+        // Hover tracking only observes the cursor, so it never captures an event the children ignored
+        let now_hovered = cursor.is_over(layout.bounds());
+        if state.is_hovered != now_hovered {
+            state.is_hovered = now_hovered;
+            let message = if now_hovered {
+                self.on_hover.as_ref().map(OnHover::get)
+            } else {
+                self.on_exit.as_ref().map(OnExit::get)
+            };
+            if let Some(message) = message {
+                shell.publish(message);
             }
         }
-        else {
-            match tree.state {
-                tree::State::None => {
-                    // eprintln!("State Empty");
-                    return event::Status::Ignored;
-                }
-                tree::State::Some(_) => {
-                    let state = tree.state.downcast_mut::<State>();
-                    // eprintln!("Something");
-                    let was_hovered = state.is_hovered;
-                    let mut now_hovered = false;
-                    if let Some(position) = cursor.position() {
-                        now_hovered = layout.bounds().contains(position);
-                    }
 
-                    match (was_hovered, now_hovered) {
-                        (true, false) => {
-                            state.is_hovered = now_hovered;
-                            return event::Status::Ignored;
-                        }
-                        (false, true) => {
-                            state.is_hovered = now_hovered;
-                            return event::Status::Ignored;
-                        }
-                        _ => {
-                            return event::Status::Ignored;
-                        }
+        // A click anywhere else moves focus away, even when another widget handled it
+        if let event::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event
+            && !cursor.is_over(layout.bounds())
+        {
+            state.is_focused = false;
+        }
+
+        let mut status = status;
+        if self.is_focusable() && status == event::Status::Ignored {
+            match event {
+                event::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if cursor.is_over(layout.bounds()) => {
+                    state.is_focused = true;
+                    if self.is_pressable() {
+                        self.press(state, shell);
+                        status = event::Status::Captured;
                     }
-                }
+                },
+                // Letting go outside the bounds cancels the press instead of completing it
+                event::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) if state.is_pressed => {
+                    if cursor.is_over(layout.bounds()) {
+                        self.release(state, shell);
+                    } else {
+                        state.is_pressed = false;
+                    }
+                    status = event::Status::Captured;
+                },
+                event::Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(key::Named::Enter | key::Named::Space), .. })
+                    if self.is_pressable() && state.is_focused && !state.is_pressed =>
+                {
+                    self.press(state, shell);
+                    status = event::Status::Captured;
+                },
+                event::Event::Keyboard(keyboard::Event::KeyReleased { key: keyboard::Key::Named(key::Named::Enter | key::Named::Space), .. })
+                    if state.is_pressed =>
+                {
+                    self.release(state, shell);
+                    status = event::Status::Captured;
+                },
+                _ => {},
+            }
+        }
+
+        let widget_status = self.status(state);
+        if state.status != Some(widget_status) {
+            if state.status.is_some() && !self.transition.is_zero() {
+                state.animation = Some(Animation {
+                    from: state.drawn.get(),
+                    started: Instant::now(),
+                });
             }
-            event::Status::Captured
+            state.status = Some(widget_status);
+            shell.request_redraw(window::RedrawRequest::NextFrame);
         }
 
+        status
     }
-    fn draw(&self, tree: &Tree, renderer: &mut Renderer, theme: &Theme, _style: &renderer::Style, layout: Layout, cursor: Cursor, rect: &Rectangle) {
+
+    fn mouse_interaction(&self, tree: &Tree, layout: Layout<'_>, cursor: Cursor, viewport: &Rectangle, renderer: &Renderer) -> mouse::Interaction {
+        self.content
+            .iter()
+            .zip(&tree.children)
+            .zip(layout.children())
+            .map(|((child, state), layout)| {
+                child.as_widget().mouse_interaction(state, layout, cursor, viewport, renderer)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn draw(&self, tree: &Tree, renderer: &mut Renderer, theme: &Theme, _style: &renderer::Style, layout: Layout, cursor: Cursor, viewport: &Rectangle) {
         let bounds = layout.bounds();
-        let content_layout = layout.children().next().unwrap();
-        let is_mouse_over = cursor.is_over(bounds);
-        
-        let status = if self.on_hover.is_none() {
-            Status::Disabled
-        } else if is_mouse_over && tree.state.downcast_ref::<State>().is_hovered{
-            Status::Hovered
-        } else {
-            Status::NotHovered
+        let state = tree.state.downcast_ref::<State>();
+
+        let target = theme.style(&self.theme, self.status(state));
+        let style = match state.animation {
+            Some(Animation { from: Some(from), started }) => {
+                let elapsed = Instant::now().saturating_duration_since(started);
+                let progress = (elapsed.as_secs_f32() / self.transition.as_secs_f32()).min(1.0);
+                from.mix(target, self.easing.apply(progress))
+            },
+            _ => target,
         };
-
-        let style = theme.style(&self.theme, status);
+        state.drawn.set(Some(style));
 
         if style.background.is_some() ||
             style.border.width > 0.0 ||
@@ -349,17 +555,16 @@ where
                 .zip(layout.children())
             {
                 child.as_widget().draw(
-                    &tree.children[0],
+                    state,
                     renderer,
                     theme,
                     &renderer::Style {
                         text_color: style.text_color,
                     },
-                    content_layout,
+                    layout,
                     cursor,
-                    &rect,
+                    viewport,
                 );
-
             }
         }
 
@@ -402,6 +607,10 @@ impl <'a, Message, Theme, Renderer: iced_core::Renderer> FromIterator <Element<'
     }
 }
 
+pub(crate) const DEFAULT_SPACING: f32 = 2.0;
+
+pub(crate) const DEFAULT_TRANSITION: Duration = Duration::from_millis(150);
+
 pub(crate) const DEFAULT_PADDING: Padding = Padding {
     top: 5.0,
     bottom: 5.0,
@@ -409,11 +618,58 @@ pub(crate) const DEFAULT_PADDING: Padding = Padding {
     left: 10.0,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Axis {
+    #[default]
+    Row,
+    Column,
+}
+
+impl From<Axis> for layout::flex::Axis {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::Row => layout::flex::Axis::Horizontal,
+            Axis::Column => layout::flex::Axis::Vertical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    #[default]
+    EaseOut,
+    EaseInOut,
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            },
+            Easing::Custom(curve) => curve(t),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Status{
     Disabled,
     Hovered,
     NotHovered,
+    Pressed,
+    Focused,
 }
 
 
@@ -428,10 +684,43 @@ pub struct Style {
 impl Style {
     pub fn with_border_color(self, border: Border) -> Self {
         Self {
-            border: border,
+            border,
             ..self
         }
     }
+
+    pub fn mix(self, to: Style, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        Self {
+            background: mix_background(self.background, to.background, t),
+            text_color: mix_color(self.text_color, to.text_color, t),
+            border: Border {
+                color: mix_color(self.border.color, to.border.color, t),
+                width: lerp(self.border.width, to.border.width),
+                radius: Radius {
+                    top_left: lerp(self.border.radius.top_left, to.border.radius.top_left),
+                    top_right: lerp(self.border.radius.top_right, to.border.radius.top_right),
+                    bottom_right: lerp(self.border.radius.bottom_right, to.border.radius.bottom_right),
+                    bottom_left: lerp(self.border.radius.bottom_left, to.border.radius.bottom_left),
+                },
+            },
+            shadow: Shadow {
+                color: mix_color(self.shadow.color, to.shadow.color, t),
+                offset: Vector::new(lerp(self.shadow.offset.x, to.shadow.offset.x), lerp(self.shadow.offset.y, to.shadow.offset.y)),
+                blur_radius: lerp(self.shadow.blur_radius, to.shadow.blur_radius),
+            },
+        }
+    }
+}
+
+fn mix_background(from: Option<Background>, to: Option<Background>, t: f32) -> Option<Background> {
+    match (from, to) {
+        (Some(Background::Color(from)), Some(Background::Color(to))) => Some(Background::Color(mix_color(from, to, t))),
+        (None, Some(Background::Color(to))) => Some(Background::Color(mix_color(Color { a: 0.0, ..to }, to, t))),
+        (Some(Background::Color(from)), None) => Some(Background::Color(mix_color(from, Color { a: 0.0, ..from }, t))),
+        // Gradients can't be blended channel by channel, so they swap over at the midpoint
+        (from, to) => if t < 0.5 { from } else { to },
+    }
 }
 
 impl Default for Style {
@@ -468,63 +757,79 @@ impl Catalog for Theme {
 }
 
 pub fn primary(theme: &Theme, status: Status) -> Style {
-    let _palette = theme.extended_palette();
+    let palette = theme.extended_palette();
+    let base = Style {
+        background: Some(Background::Color(Color::TRANSPARENT)),
+        text_color: palette.background.base.text,
+        border: Border {color: palette.background.strong.color, width: 2.0, radius: equal_radius(5)},
+        ..Default::default()
+    };
     match status {
         Status::Disabled => {
             Style {
-                background: Some(Background::Color(Color::TRANSPARENT)),
-                text_color: get_rgb_color(255, 255, 255),
-                border: Border {color: get_rgb_color(100, 100, 100), width: 2.0, radius: equal_radius(5)},
-                ..Default::default()
+                text_color: palette.background.strong.text,
+                border: Border {color: palette.background.weak.color, ..base.border},
+                ..base
             }
         },
         Status::Hovered => {
             Style {
-                background: Some(Background::Color(Color::TRANSPARENT)),
-                text_color: get_rgb_color(255, 255, 255),
-                border: Border {color: get_rgb_color(0, 0, 255), width: 2.0, radius: equal_radius(5)},
-                ..Default::default()
+                border: Border {color: palette.primary.base.color, ..base.border},
+                ..base
             }
         },
-        Status::NotHovered => {
+        Status::NotHovered => base,
+        Status::Pressed => {
             Style {
-                background: Some(Background::Color(Color::TRANSPARENT)),
-                text_color: get_rgb_color(255, 255, 255),
-                border: Border {color: get_rgb_color(150, 150, 150), width: 2.0, radius: equal_radius(5)},
-                ..Default::default()
+                background: Some(Background::Color(Color { a: 0.2, ..palette.primary.weak.color })),
+                border: Border {color: palette.primary.strong.color, width: 3.0, ..base.border},
+                ..base
             }
-        }
+        },
+        Status::Focused => {
+            Style {
+                border: Border {color: palette.primary.base.color, ..base.border},
+                shadow: focus_ring(palette.primary.base.color),
+                ..base
+            }
+        },
+    }
+}
+
+pub fn focus_ring(color: Color) -> Shadow {
+    Shadow {
+        color: Color { a: 0.6, ..color },
+        offset: Vector::ZERO,
+        blur_radius: 6.0,
     }
 }
 
 pub fn auto_style(unhov_color: Color, hov_color: Color, width: i32, radius: u32) -> impl Fn(&Theme, Status) -> Style {
-    move |_theme: &Theme, status | {
-        match status {
-            Status::Disabled => {
-                let border = Border {
-                    color: unhov_color,
-                    width: width as f32,
-                    radius: equal_radius(radius),
-                };
-                Style::with_border_color(Style{..Default::default()}, border)
-            },
-            Status::Hovered => {
-                let border = Border {
-                    color: hov_color,
-                    width: width as f32,
-                    radius: equal_radius(radius),
-                };
-                Style::with_border_color(Style{..Default::default()}, border)
-            },
-            Status::NotHovered => {
-                let border = Border {
-                    color: unhov_color,
-                    width: width as f32,
-                    radius: equal_radius(radius),
-                };
-                Style::with_border_color(Style{..Default::default()}, border)
-            },
+    move |theme: &Theme, status | {
+        let text_color = theme.extended_palette().background.base.text;
+        let (color, width, shadow) = match status {
+            Status::Disabled | Status::NotHovered => (unhov_color, width, Shadow::default()),
+            Status::Hovered => (hov_color, width, Shadow::default()),
+            Status::Pressed => (hov_color, width + 1, Shadow::default()),
+            Status::Focused => (hov_color, width, focus_ring(hov_color)),
+        };
+        let border = Border {
+            color,
+            width: width as f32,
+            radius: equal_radius(radius),
+        };
+        Style {
+            text_color,
+            shadow,
+            ..Style::with_border_color(Style{..Default::default()}, border)
         }
     }
 }
 
+// Same shape as `auto_style`, with the border colors taken from the active palette
+pub fn palette_style(width: i32, radius: u32) -> impl Fn(&Theme, Status) -> Style {
+    move |theme: &Theme, status| {
+        let palette = theme.extended_palette();
+        auto_style(palette.background.strong.color, palette.primary.base.color, width, radius)(theme, status)
+    }
+}
@@ -1 +1,3 @@
-pub mod hov_container_row;
\ No newline at end of file
+pub mod history_graph;
+pub mod hov_container_row;
+pub mod numeric_input;
\ No newline at end of file
@@ -0,0 +1,399 @@
+use std::{ops::RangeInclusive, rc::Rc};
+
+use iced::{
+    widget::{button, text, text_input, Column, Row},
+    Alignment, Element, Length, Renderer, Theme,
+};
+use iced_core::{
+    event::{self, Event},
+    layout::{self, Layout},
+    mouse, overlay, renderer,
+    widget::{tree::Tree, Operation, Widget},
+    Clipboard, Rectangle, Shell, Size, Vector,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    NotANumber,
+    OutOfRange,
+    Rejected,
+}
+
+pub type StyleFn<'a> = Rc<dyn Fn(&Theme, text_input::Status) -> text_input::Style + 'a>;
+
+pub type ButtonStyleFn<'a> = Rc<dyn Fn(&Theme, button::Status) -> button::Style + 'a>;
+
+pub struct NumericInput<'a, Message> {
+    value: u8,
+    input: &'a str,
+    range: RangeInclusive<u8>,
+    step: u8,
+    stepper: Option<Box<dyn Fn(u8, bool) -> u8 + 'a>>,
+    clamp_on_submit: bool,
+    scroll_step: bool,
+    buttons: bool,
+    show_error: bool,
+    disabled: bool,
+    validate: Option<Box<dyn Fn(u8) -> bool + 'a>>,
+    on_input: Option<Box<dyn Fn(String) -> Message + 'a>>,
+    on_submit: Option<Box<dyn Fn(u8) -> Message + 'a>>,
+    style: StyleFn<'a>,
+    error_style: StyleFn<'a>,
+    button_style: ButtonStyleFn<'a>,
+    error_message: Option<String>,
+    width: Length,
+    size: Option<f32>,
+}
+
+impl<'a, Message> NumericInput<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    pub fn new(value: u8, input: &'a str) -> Self {
+        Self {
+            value,
+            input,
+            range: 0..=100,
+            step: 1,
+            stepper: None,
+            clamp_on_submit: true,
+            scroll_step: true,
+            buttons: true,
+            show_error: true,
+            disabled: false,
+            validate: None,
+            on_input: None,
+            on_submit: None,
+            style: Rc::new(default_style),
+            error_style: Rc::new(error_style),
+            button_style: Rc::new(button::primary),
+            error_message: None,
+            width: Length::Fixed(100.0),
+            size: None,
+        }
+    }
+
+    pub fn range(mut self, range: RangeInclusive<u8>) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn step(mut self, step: u8) -> Self {
+        self.step = step;
+        self
+    }
+
+    // Computes the next value up (true) or down (false) in place of the fixed step, e.g. for a volume curve
+    pub fn stepper(mut self, stepper: impl Fn(u8, bool) -> u8 + 'a) -> Self {
+        self.stepper = Some(Box::new(stepper));
+        self
+    }
+
+    pub fn clamp_on_submit(mut self, clamp: bool) -> Self {
+        self.clamp_on_submit = clamp;
+        self
+    }
+
+    pub fn scroll_step(mut self, enabled: bool) -> Self {
+        self.scroll_step = enabled;
+        self
+    }
+
+    pub fn buttons(mut self, shown: bool) -> Self {
+        self.buttons = shown;
+        self
+    }
+
+    pub fn show_error(mut self, shown: bool) -> Self {
+        self.show_error = shown;
+        self
+    }
+
+    // Shows the value without taking input or stepping, the handlers are kept for when it's enabled again
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    pub fn validate(mut self, validate: impl Fn(u8) -> bool + 'a) -> Self {
+        self.validate = Some(Box::new(validate));
+        self
+    }
+
+    pub fn on_input(mut self, on_input: impl Fn(String) -> Message + 'a) -> Self {
+        self.on_input = Some(Box::new(on_input));
+        self
+    }
+
+    pub fn on_submit(mut self, on_submit: impl Fn(u8) -> Message + 'a) -> Self {
+        self.on_submit = Some(Box::new(on_submit));
+        self
+    }
+
+    pub fn style(mut self, style: impl Fn(&Theme, text_input::Status) -> text_input::Style + 'a) -> Self {
+        self.style = Rc::new(style);
+        self
+    }
+
+    pub fn error_style(mut self, style: impl Fn(&Theme, text_input::Status) -> text_input::Style + 'a) -> Self {
+        self.error_style = Rc::new(style);
+        self
+    }
+
+    pub fn button_style(mut self, style: impl Fn(&Theme, button::Status) -> button::Style + 'a) -> Self {
+        self.button_style = Rc::new(style);
+        self
+    }
+
+    pub fn error_message(mut self, message: impl Into<String>) -> Self {
+        self.error_message = Some(message.into());
+        self
+    }
+
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    // Errors shown while typing are strict, clamping only happens once the value is submitted
+    pub fn error(&self) -> Option<InputError> {
+        self.check(self.input, false).err()
+    }
+
+    fn check(&self, input: &str, clamp: bool) -> Result<u8, InputError> {
+        let value = parse(input, &self.range, clamp)?;
+        match &self.validate {
+            Some(validate) if !validate(value) => Err(InputError::Rejected),
+            _ => Ok(value),
+        }
+    }
+
+    fn view(mut self) -> Element<'a, Message> {
+        if self.disabled {
+            self.on_input = None;
+            self.on_submit = None;
+        }
+        let invalid = self.error().is_some();
+        let submitted = self.check(self.input, self.clamp_on_submit).ok();
+        let (step_up, step_down) = match &self.stepper {
+            Some(stepper) => (stepper(self.value, true), stepper(self.value, false)),
+            None => (self.value.saturating_add(self.step), self.value.saturating_sub(self.step)),
+        };
+        let step_up = step_up.min(*self.range.end());
+        let step_down = step_down.max(*self.range.start());
+
+        let (on_submit, on_up, on_down) = match &self.on_submit {
+            Some(on_submit) => (submitted.map(on_submit), Some(on_submit(step_up)), Some(on_submit(step_down))),
+            None => (None, None, None),
+        };
+
+        let style = self.style;
+        let error_style = self.error_style;
+        let text_style = Rc::clone(&error_style);
+        let mut input = text_input(&self.value.to_string(), self.input)
+            .on_input_maybe(self.on_input)
+            .on_submit_maybe(on_submit)
+            .style(move |theme: &Theme, status| {
+                if invalid {
+                    error_style(theme, status)
+                } else {
+                    style(theme, status)
+                }
+            })
+            .align_x(Alignment::Center)
+            .width(self.width);
+        if let Some(size) = self.size {
+            input = input.size(size);
+        }
+
+        let row = if self.buttons {
+            let (up_style, down_style) = (Rc::clone(&self.button_style), Rc::clone(&self.button_style));
+            Row::new()
+                .push(button(" + ").on_press_maybe(on_up.clone()).style(move |theme, status| up_style(theme, status)))
+                .push(input)
+                .push(button(" - ").on_press_maybe(on_down.clone()).style(move |theme, status| down_style(theme, status)))
+                .align_y(Alignment::Center)
+                .spacing(10)
+        } else {
+            Row::new().push(input).align_y(Alignment::Center)
+        };
+
+        let row: Element<'a, Message> = match (self.scroll_step, on_up, on_down) {
+            (true, Some(up), Some(down)) => ScrollStep { content: row.into(), up, down }.into(),
+            _ => row.into(),
+        };
+
+        let error_message = self.error_message.unwrap_or_else(|| {
+            format!("Please enter a number between {} and {}!", self.range.start(), self.range.end())
+        });
+
+        Column::new()
+            .push(row)
+            .push_maybe(if invalid && self.show_error {
+                // The message shares its color with the value text of the error style
+                Some(text(error_message).style(move |theme: &Theme| text::Style {
+                    color: Some(text_style(theme, text_input::Status::Active).value),
+                }))
+            } else {None})
+            .align_x(Alignment::Center)
+            .spacing(5)
+            .into()
+    }
+}
+
+impl<'a, Message> From<NumericInput<'a, Message>> for Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    fn from(numeric_input: NumericInput<'a, Message>) -> Self {
+        numeric_input.view()
+    }
+}
+
+// Steps the value with the mouse wheel, sideways scrolling (no vertical delta) is left alone
+struct ScrollStep<'a, Message> {
+    content: Element<'a, Message>,
+    up: Message,
+    down: Message,
+}
+
+impl<Message: Clone> Widget<Message, Theme, Renderer> for ScrollStep<'_, Message> {
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn draw(&self, tree: &Tree, renderer: &mut Renderer, theme: &Theme, style: &renderer::Style, layout: Layout<'_>, cursor: mouse::Cursor, viewport: &Rectangle) {
+        self.content.as_widget().draw(&tree.children[0], renderer, theme, style, layout, cursor, viewport);
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn operate(&self, tree: &mut Tree, layout: Layout<'_>, renderer: &Renderer, operation: &mut dyn Operation) {
+        self.content.as_widget().operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(&mut self, tree: &mut Tree, event: Event, layout: Layout<'_>, cursor: mouse::Cursor, renderer: &Renderer, clipboard: &mut dyn Clipboard, shell: &mut Shell<'_, Message>, viewport: &Rectangle) -> event::Status {
+        if let event::Status::Captured = self.content.as_widget_mut().on_event(&mut tree.children[0], event.clone(), layout, cursor, renderer, clipboard, shell, viewport) {
+            return event::Status::Captured;
+        }
+        if let Event::Mouse(mouse::Event::WheelScrolled { delta }) = event && cursor.is_over(layout.bounds()) {
+            let y = match delta {
+                mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => y,
+            };
+            if y != 0.0 {
+                shell.publish(if y > 0.0 {self.up.clone()} else {self.down.clone()});
+                return event::Status::Captured;
+            }
+        }
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(&self, tree: &Tree, layout: Layout<'_>, cursor: mouse::Cursor, viewport: &Rectangle, renderer: &Renderer) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(&tree.children[0], layout, cursor, viewport, renderer)
+    }
+
+    fn overlay<'b>(&'b mut self, tree: &'b mut Tree, layout: Layout<'_>, renderer: &Renderer, translation: Vector) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content.as_widget_mut().overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a, Message: Clone + 'a> From<ScrollStep<'a, Message>> for Element<'a, Message> {
+    fn from(scroll_step: ScrollStep<'a, Message>) -> Self {
+        Element::new(scroll_step)
+    }
+}
+
+pub fn parse(input: &str, range: &RangeInclusive<u8>, clamp: bool) -> Result<u8, InputError> {
+    let value = input.trim().parse::<i64>().map_err(|_| InputError::NotANumber)?;
+    let (min, max) = (*range.start() as i64, *range.end() as i64);
+    if (min..=max).contains(&value) {
+        Ok(value as u8)
+    } else if clamp {
+        Ok(value.clamp(min, max) as u8)
+    } else {
+        Err(InputError::OutOfRange)
+    }
+}
+
+pub fn default_style(theme: &Theme, status: text_input::Status) -> text_input::Style {
+    text_input::default(theme, status)
+}
+
+pub fn error_style(theme: &Theme, status: text_input::Status) -> text_input::Style {
+    let danger = theme.extended_palette().danger;
+    text_input::Style {
+        border: iced::Border{color: danger.base.color, width: 1.0, ..text_input::default(theme, status).border},
+        value: danger.base.color,
+        selection: danger.weak.color,
+        ..text_input::default(theme, status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_in_range_parse_either_way() {
+        assert_eq!(parse("0", &(0..=100), false), Ok(0));
+        assert_eq!(parse("100", &(0..=100), false), Ok(100));
+        assert_eq!(parse("42", &(10..=60), true), Ok(42));
+    }
+
+    #[test]
+    fn out_of_range_values_are_refused_unless_clamped() {
+        assert_eq!(parse("101", &(0..=100), false), Err(InputError::OutOfRange));
+        assert_eq!(parse("5", &(10..=60), false), Err(InputError::OutOfRange));
+        assert_eq!(parse("-3", &(0..=100), false), Err(InputError::OutOfRange));
+        assert_eq!(parse("101", &(0..=100), true), Ok(100));
+        assert_eq!(parse("5", &(10..=60), true), Ok(10));
+        assert_eq!(parse("-3", &(0..=100), true), Ok(0));
+        // Past what a u8 holds still clamps instead of wrapping
+        assert_eq!(parse("300", &(0..=255), true), Ok(255));
+        assert_eq!(parse("99999999999", &(0..=100), true), Ok(100));
+    }
+
+    #[test]
+    fn anything_but_a_whole_number_is_not_a_number() {
+        for input in ["", " ", "abc", "12a", "4.5", "1 2", "+-1"] {
+            assert_eq!(parse(input, &(0..=100), true), Err(InputError::NotANumber), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        assert_eq!(parse(" 42 ", &(0..=100), false), Ok(42));
+        assert_eq!(parse("\t7\n", &(0..=100), false), Ok(7));
+    }
+
+    #[test]
+    fn errors_while_typing_are_strict_and_submitting_clamps() {
+        let input = NumericInput::<()>::new(50, "150");
+        assert_eq!(input.error(), Some(InputError::OutOfRange));
+        assert_eq!(input.check("150", input.clamp_on_submit), Ok(100));
+        let strict = NumericInput::<()>::new(50, "150").clamp_on_submit(false);
+        assert_eq!(strict.check("150", strict.clamp_on_submit), Err(InputError::OutOfRange));
+    }
+
+    #[test]
+    fn validation_runs_on_the_clamped_value() {
+        let input = NumericInput::<()>::new(50, "150").validate(|value| value < 100);
+        assert_eq!(input.check("150", true), Err(InputError::Rejected));
+        assert_eq!(input.check("99", true), Ok(99));
+    }
+}
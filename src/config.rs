@@ -0,0 +1,191 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::vol_ctl::{curve::Curve, dosimeter::DoseStandard, ramp::Ramp, schedule::Schedule, sleep_timer::SleepSettings, tamper::TamperSettings};
+
+const APP_DIR: &str = "vol-limiter";
+const SETTINGS_FILE: &str = "config.toml";
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// Falls back to the local data directory on platforms without a dedicated state directory (macOS, Windows)
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir().or_else(dirs::data_local_dir).map(|dir| dir.join(APP_DIR))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: String,
+    // The limiter and its limit as they were last left, restored at startup
+    pub limit: LevelSettings,
+    pub dosimeter: DosimeterSettings,
+    // Loudness in dB SPL at 100% volume, keyed by device name
+    pub sensitivity: BTreeMap<String, f32>,
+    pub schedule: Schedule,
+    pub ramp: Ramp,
+    pub sleep: SleepSettings,
+    pub slew: SlewSettings,
+    // Lowest volume the limiter allows, e.g. to keep alarms audible
+    pub floor: LevelSettings,
+    // Volume the limiter holds regardless of changes
+    pub lock: LevelSettings,
+    // Whether the floor and lock let a mute through
+    pub allow_mute: bool,
+    // Volume a device is brought down to when it becomes the output, keyed by device name
+    pub safe_volume: BTreeMap<String, u8>,
+    pub startup_volume: LevelSettings,
+    pub input: InputSettings,
+    // Highest output level allowed, anything past 100% is software amplification
+    pub max_volume: u8,
+    // Show volume levels in dBFS instead of percent
+    pub show_db: bool,
+    // Mapping from slider position to volume
+    pub curve: Curve,
+    // Where earlier versions kept the PIN hash, only read to move it out (see pin::load_hash)
+    #[serde(skip_serializing)]
+    pub pin_hash: Option<String>,
+    pub tamper: TamperSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: String::from("System"),
+            limit: LevelSettings { enabled: false, percent: 20 },
+            dosimeter: DosimeterSettings::default(),
+            sensitivity: BTreeMap::new(),
+            schedule: Schedule::default(),
+            ramp: Ramp::default(),
+            sleep: SleepSettings::default(),
+            slew: SlewSettings::default(),
+            floor: LevelSettings { enabled: false, percent: 10 },
+            lock: LevelSettings { enabled: false, percent: 30 },
+            allow_mute: true,
+            safe_volume: BTreeMap::new(),
+            startup_volume: LevelSettings { enabled: false, percent: 20 },
+            input: InputSettings::default(),
+            max_volume: 100,
+            show_db: false,
+            curve: Curve::Linear,
+            pin_hash: None,
+            tamper: TamperSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DosimeterSettings {
+    pub standard: DoseStandard,
+    pub tighten: bool,
+}
+
+// Fastest the volume may rise while the limiter runs, in percent per second
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlewSettings {
+    pub enabled: bool,
+    pub rate: u8,
+}
+
+impl Default for SlewSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: 20,
+        }
+    }
+}
+
+impl SlewSettings {
+    pub fn rate(&self) -> Option<u8> {
+        self.enabled.then_some(self.rate)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelSettings {
+    pub enabled: bool,
+    pub percent: u8,
+}
+
+// Limits for the default capture device, so conferencing apps can't keep moving the microphone gain
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    pub ceiling: LevelSettings,
+    pub floor: LevelSettings,
+    pub lock: LevelSettings,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            ceiling: LevelSettings { enabled: false, percent: 80 },
+            floor: LevelSettings { enabled: false, percent: 30 },
+            lock: LevelSettings { enabled: false, percent: 60 },
+        }
+    }
+}
+
+impl LevelSettings {
+    pub fn level(&self) -> Option<u8> {
+        self.enabled.then_some(self.percent)
+    }
+}
+
+impl Settings {
+    // Defaults when there is no file yet, an error when it can't be read or parsed so it isn't overwritten
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_dir().map(|dir| dir.join(SETTINGS_FILE)) else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).map_err(|error| format!("invalid settings in {}: {}", path.display(), error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(format!("failed to read {}: {}", path.display(), error)),
+        }
+    }
+
+    pub fn exists() -> bool {
+        config_dir().is_some_and(|dir| dir.join(SETTINGS_FILE).exists())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = config_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        fs::create_dir_all(&dir)?;
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(dir.join(SETTINGS_FILE), contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limiter_state_survives_a_restart() {
+        let settings = Settings { limit: LevelSettings { enabled: true, percent: 35 }, ..Settings::default() };
+        let restored: Settings = toml::from_str(&toml::to_string_pretty(&settings).unwrap()).unwrap();
+        assert_eq!(restored.limit, settings.limit);
+    }
+
+    #[test]
+    fn pin_hash_is_read_but_never_written() {
+        let settings: Settings = toml::from_str("pin_hash = \"hash\"").unwrap();
+        assert_eq!(settings.pin_hash.as_deref(), Some("hash"));
+        assert!(!toml::to_string_pretty(&settings).unwrap().contains("pin_hash"));
+    }
+
+    #[test]
+    fn missing_fields_take_their_defaults() {
+        let settings: Settings = toml::from_str("show_db = true").unwrap();
+        assert!(settings.show_db);
+        assert_eq!(settings.limit, Settings::default().limit);
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use serde::Serialize;
+
+use crate::{
+    audit::{Entry, Event},
+    vol_ctl::history::History,
+};
+
+// Bumped whenever a column or field changes meaning or goes away, new ones are only ever added at the end.
+//
+// CSV writes three files, each with a header row:
+//   <prefix>-samples.csv  minute, device, volume, ceiling, clamps, seconds, seconds_at_ceiling
+//   <prefix>-clamps.csv   time, device, before, after
+//   <prefix>-devices.csv  device, seconds, seconds_at_ceiling, clamps
+// JSON writes <prefix>.json holding {"version", "from", "to", "samples", "clamps", "devices"},
+// the last three being arrays of objects with the same fields as the CSV columns.
+//
+// Times are local, formatted as 2026-01-31T21:05:00. Volumes are percents, ceiling is empty (null in JSON)
+// while the limiter was off. Samples cover one minute on one output device, volume being its highest.
+pub const VERSION: u32 = 1;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Csv, Format::Json];
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Csv => write!(f, "CSV"),
+            Format::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format \"{}\", expected csv or json", format)),
+        }
+    }
+}
+
+// Both days included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Range {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Result<Self, String> {
+        if from > to {
+            return Err(format!("the range starts on {} after it ends on {}", from, to));
+        }
+        Ok(Self { from, to })
+    }
+
+    // The seven days up to and including `today`, all the history that is kept
+    pub fn last_week(today: NaiveDate) -> Self {
+        Self { from: today - TimeDelta::days(6), to: today }
+    }
+
+    pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+        NaiveDate::parse_from_str(date.trim(), DATE_FORMAT).map_err(|error| format!("invalid date \"{}\", expected YYYY-MM-DD: {}", date, error))
+    }
+
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        (self.from..=self.to).contains(&time.date())
+    }
+
+    fn start(&self) -> NaiveDateTime {
+        self.from.and_time(Default::default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SampleRow {
+    pub minute: String,
+    pub device: String,
+    pub volume: u8,
+    pub ceiling: Option<u8>,
+    pub clamps: u16,
+    pub seconds: u64,
+    pub seconds_at_ceiling: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClampRow {
+    pub time: String,
+    pub device: String,
+    pub before: u8,
+    pub after: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceRow {
+    pub device: String,
+    pub seconds: u64,
+    pub seconds_at_ceiling: u64,
+    pub clamps: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Export {
+    pub version: u32,
+    pub from: String,
+    pub to: String,
+    pub samples: Vec<SampleRow>,
+    pub clamps: Vec<ClampRow>,
+    pub devices: Vec<DeviceRow>,
+}
+
+impl Export {
+    // Samples come from the volume history, clamp events from the audit log
+    pub fn collect(range: Range, history: &History, audit: &[Entry]) -> Self {
+        let samples: Vec<_> = history.since(range.start()).filter(|sample| range.contains(sample.minute)).collect();
+        let mut devices: Vec<DeviceRow> = Vec::new();
+        for sample in &samples {
+            let index = match devices.iter().position(|row| row.device == sample.device) {
+                Some(index) => index,
+                None => {
+                    devices.push(DeviceRow { device: sample.device.clone(), seconds: 0, seconds_at_ceiling: 0, clamps: 0 });
+                    devices.len() - 1
+                },
+            };
+            devices[index].seconds += sample.observed.as_secs();
+            devices[index].seconds_at_ceiling += sample.at_ceiling.as_secs();
+            devices[index].clamps += sample.clamps as u32;
+        }
+        let clamps = audit.iter()
+            .filter(|entry| range.contains(entry.time))
+            .filter_map(|entry| match &entry.event {
+                Event::Clamp { before, after, device } => Some(ClampRow {
+                    time: entry.time.format(TIME_FORMAT).to_string(),
+                    device: device.clone(),
+                    before: *before,
+                    after: *after,
+                }),
+                _ => None,
+            })
+            .collect();
+        Self {
+            version: VERSION,
+            from: range.from.format(DATE_FORMAT).to_string(),
+            to: range.to.format(DATE_FORMAT).to_string(),
+            samples: samples.into_iter().map(|sample| SampleRow {
+                minute: sample.minute.format(TIME_FORMAT).to_string(),
+                device: sample.device.clone(),
+                volume: sample.volume,
+                ceiling: sample.ceiling,
+                clamps: sample.clamps,
+                seconds: sample.observed.as_secs(),
+                seconds_at_ceiling: sample.at_ceiling.as_secs(),
+            }).collect(),
+            clamps,
+            devices,
+        }
+    }
+
+    // Writes into `dir`, returning the files it created or replaced
+    pub fn write(&self, dir: &Path, format: Format) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let prefix = format!("vol-limiter-{}-to-{}", self.from, self.to);
+        let files = match format {
+            Format::Json => vec![(
+                dir.join(format!("{}.json", prefix)),
+                serde_json::to_string_pretty(self).map_err(io::Error::other)?,
+            )],
+            Format::Csv => vec![
+                (dir.join(format!("{}-samples.csv", prefix)), csv(
+                    &["minute", "device", "volume", "ceiling", "clamps", "seconds", "seconds_at_ceiling"],
+                    self.samples.iter().map(|row| vec![
+                        row.minute.clone(),
+                        row.device.clone(),
+                        row.volume.to_string(),
+                        row.ceiling.map(|ceiling| ceiling.to_string()).unwrap_or_default(),
+                        row.clamps.to_string(),
+                        row.seconds.to_string(),
+                        row.seconds_at_ceiling.to_string(),
+                    ]),
+                )),
+                (dir.join(format!("{}-clamps.csv", prefix)), csv(
+                    &["time", "device", "before", "after"],
+                    self.clamps.iter().map(|row| vec![row.time.clone(), row.device.clone(), row.before.to_string(), row.after.to_string()]),
+                )),
+                (dir.join(format!("{}-devices.csv", prefix)), csv(
+                    &["device", "seconds", "seconds_at_ceiling", "clamps"],
+                    self.devices.iter().map(|row| vec![row.device.clone(), row.seconds.to_string(), row.seconds_at_ceiling.to_string(), row.clamps.to_string()]),
+                )),
+            ],
+        };
+        files.into_iter()
+            .map(|(path, contents)| fs::write(&path, contents).map(|_| path))
+            .collect()
+    }
+}
+
+fn csv(header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut contents = header.join(",") + "\n";
+    for row in rows {
+        contents += &row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",");
+        contents += "\n";
+    }
+    contents
+}
+
+// RFC 4180 quoting, device names can hold commas
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{test_util::{at, date, temp_path}, vol_ctl::history::Sample};
+
+    use super::*;
+
+    fn sample(minute: NaiveDateTime, device: &str, volume: u8, ceiling: Option<u8>, clamps: u16) -> Sample {
+        Sample {
+            minute,
+            volume,
+            ceiling,
+            clamps,
+            observed: Duration::from_secs(60),
+            at_ceiling: Duration::from_secs(if ceiling == Some(volume) {60} else {0}),
+            device: device.to_owned(),
+        }
+    }
+
+    fn export() -> Export {
+        let history = History::from_samples([
+            sample(at(14, 23, 59), "speakers", 30, Some(40), 0),
+            sample(at(15, 20, 0), "speakers", 40, Some(40), 2),
+            sample(at(15, 20, 1), "Headphones, USB", 25, None, 0),
+            sample(at(16, 8, 0), "speakers", 35, Some(40), 1),
+            sample(at(17, 0, 0), "speakers", 50, None, 0),
+        ]);
+        let audit = [
+            Entry { time: at(14, 23, 59), event: Event::Clamp { before: 60, after: 40, device: String::from("speakers") } },
+            Entry { time: at(15, 20, 0), event: Event::Clamp { before: 55, after: 40, device: String::from("speakers") } },
+            Entry { time: at(15, 21, 0), event: Event::OverrideEnded },
+        ];
+        Export::collect(Range::new(date(15), date(16)).unwrap(), &history, &audit)
+    }
+
+    #[test]
+    fn collect_keeps_the_range_and_sums_per_device() {
+        let export = export();
+        assert_eq!((export.version, export.from.as_str(), export.to.as_str()), (VERSION, "2026-03-15", "2026-03-16"));
+        assert_eq!(export.samples.iter().map(|row| row.minute.as_str()).collect::<Vec<_>>(), ["2026-03-15T20:00:00", "2026-03-15T20:01:00", "2026-03-16T08:00:00"]);
+        assert_eq!(export.devices, [
+            DeviceRow { device: String::from("speakers"), seconds: 120, seconds_at_ceiling: 60, clamps: 3 },
+            DeviceRow { device: String::from("Headphones, USB"), seconds: 60, seconds_at_ceiling: 0, clamps: 0 },
+        ]);
+    }
+
+    #[test]
+    fn collect_takes_only_clamps_from_the_audit_log() {
+        assert_eq!(export().clamps, [ClampRow { time: String::from("2026-03-15T20:00:00"), device: String::from("speakers"), before: 55, after: 40 }]);
+    }
+
+    #[test]
+    fn csv_files_have_headers_quoting_and_empty_ceilings() {
+        let dir = temp_path("export-csv");
+        let files = export().write(&dir, Format::Csv).unwrap();
+        let names: Vec<_> = files.iter().filter_map(|path| path.file_name()?.to_str()).collect();
+        assert_eq!(names, [
+            "vol-limiter-2026-03-15-to-2026-03-16-samples.csv",
+            "vol-limiter-2026-03-15-to-2026-03-16-clamps.csv",
+            "vol-limiter-2026-03-15-to-2026-03-16-devices.csv",
+        ]);
+        let samples = fs::read_to_string(&files[0]).unwrap();
+        let lines: Vec<_> = samples.lines().collect();
+        assert_eq!(lines[0], "minute,device,volume,ceiling,clamps,seconds,seconds_at_ceiling");
+        assert_eq!(lines[2], "2026-03-15T20:01:00,\"Headphones, USB\",25,,0,60,0");
+        assert_eq!(fs::read_to_string(&files[2]).unwrap().lines().nth(1), Some("speakers,120,60,3"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn json_has_the_version_and_null_ceilings() {
+        let dir = temp_path("export-json");
+        let files = export().write(&dir, Format::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&files[0]).unwrap()).unwrap();
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["samples"][0]["ceiling"], 40);
+        assert!(json["samples"][1]["ceiling"].is_null());
+        assert_eq!(json["devices"][0]["clamps"], 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("speakers"), "speakers");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn ranges_and_formats_parse() {
+        assert_eq!(Range::new(date(16), date(15)).map_err(|_| ()), Err(()));
+        assert_eq!(Range::last_week(date(16)), Range { from: date(10), to: date(16) });
+        assert_eq!(Range::parse_date(" 2026-03-16 "), Ok(date(16)));
+        assert!(Range::parse_date("16.03.2026").is_err());
+        assert_eq!("JSON".parse(), Ok(Format::Json));
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
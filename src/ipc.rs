@@ -0,0 +1,284 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+
+use crate::{config, pin, vol_ctl::sleep_timer::{AfterSleep, SleepSettings}};
+
+const REQUESTS_FILE: &str = "requests";
+// Requests left behind while the app wasn't running are dropped rather than acted on at the next start
+const STALE_AFTER: Duration = Duration::from_secs(60);
+// How far ahead of the app's clock a proof may be dated
+const CLOCK_SKEW: u64 = 5;
+
+// Requests from the command line to the running app, one per line in a file the app drains while polling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Request {
+    Sleep(SleepSettings),
+    CancelSleep,
+    Lock,
+    // Ceiling in percent for a number of minutes
+    Override(u8, u8),
+    CancelOverride,
+}
+
+impl Request {
+    // Whether the app refuses the request while locked unless it carries the PIN
+    pub fn is_protected(&self) -> bool {
+        match self {
+            // Ending a sleep timer early brings the volume back up, ending an override undoes what a parent granted
+            Request::CancelSleep | Request::Override(..) | Request::CancelOverride => true,
+            Request::Sleep(_) | Request::Lock => false,
+        }
+    }
+}
+
+// A request as read back by the app, with the proof that the sender knew the PIN
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub request: Request,
+    pub proof: Option<Proof>,
+}
+
+// Stands in for the PIN, which never reaches the requests file. It signs one request at one time, the nonce keeps
+// a copied line from being replayed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Proof {
+    // Seconds since the Unix epoch
+    pub time: u64,
+    pub nonce: String,
+    pub mac: String,
+}
+
+impl Proof {
+    pub fn sign(request: Request, hash: &str) -> Self {
+        let mut nonce = [0; 16];
+        OsRng.fill_bytes(&mut nonce);
+        Self::sign_at(request, hash, unix_time(), pin::to_hex(&nonce))
+    }
+
+    fn sign_at(request: Request, hash: &str, time: u64, nonce: String) -> Self {
+        let mac = pin::sign(hash, &signed(request, time, &nonce));
+        Self { time, nonce, mac }
+    }
+
+    // Proofs older than a left-over request, or dated too far ahead, don't count
+    pub fn verify(&self, request: Request, hash: &str, now: u64) -> bool {
+        now.saturating_sub(STALE_AFTER.as_secs()) <= self.time
+            && self.time <= now + CLOCK_SKEW
+            && pin::check(hash, &signed(request, self.time, &self.nonce), &self.mac)
+    }
+}
+
+fn signed(request: Request, time: u64, nonce: &str) -> String {
+    format!("{}\t{}\t{}", request, time, nonce)
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+// Nonces of the proofs accepted while they could still verify
+#[derive(Debug, Default)]
+pub struct Replays {
+    seen: VecDeque<(u64, String)>,
+}
+
+impl Replays {
+    pub fn first_use(&mut self, proof: &Proof, now: u64) -> bool {
+        self.seen.retain(|(time, _)| time + STALE_AFTER.as_secs() + CLOCK_SKEW >= now);
+        if self.seen.iter().any(|(_, nonce)| *nonce == proof.nonce) {
+            return false;
+        }
+        self.seen.push_back((proof.time, proof.nonce.clone()));
+        true
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::Sleep(sleep) => write!(f, "sleep {} {} {} {}", sleep.minutes, sleep.fade_minutes, sleep.floor, sleep.after),
+            Request::CancelSleep => write!(f, "cancel-sleep"),
+            Request::Lock => write!(f, "lock"),
+            Request::Override(percent, minutes) => write!(f, "override {} {}", percent, minutes),
+            Request::CancelOverride => write!(f, "cancel-override"),
+        }
+    }
+}
+
+impl FromStr for Request {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let number = |word: &str| word.parse::<u8>().map_err(|error| format!("invalid number \"{}\": {}", word, error));
+        match words.as_slice() {
+            ["sleep", minutes, fade, floor, after] => Ok(Request::Sleep(SleepSettings {
+                minutes: number(minutes)?,
+                fade_minutes: number(fade)?,
+                floor: number(floor)?,
+                after: after.parse::<AfterSleep>()?,
+            })),
+            ["cancel-sleep"] => Ok(Request::CancelSleep),
+            ["lock"] => Ok(Request::Lock),
+            ["override", percent, minutes] => Ok(Request::Override(number(percent)?, number(minutes)?)),
+            ["cancel-override"] => Ok(Request::CancelOverride),
+            _ => Err(format!("unknown request \"{}\"", s)),
+        }
+    }
+}
+
+fn path() -> io::Result<PathBuf> {
+    config::state_dir()
+        .map(|dir| dir.join(REQUESTS_FILE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > STALE_AFTER))
+}
+
+// The proof follows the request after a tab
+pub fn send(request: Request, proof: Option<&Proof>) -> io::Result<()> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Requests the app never picked up are dropped rather than left lying around
+    if is_stale(&path) {
+        let _ = fs::remove_file(&path);
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", line(request, proof))
+}
+
+fn line(request: Request, proof: Option<&Proof>) -> String {
+    match proof {
+        Some(proof) => format!("{}\t{} {} {}", request, proof.time, proof.nonce, proof.mac),
+        None => request.to_string(),
+    }
+}
+
+fn parse(line: &str) -> Result<Envelope, String> {
+    let Some((request, proof)) = line.split_once('\t') else {
+        return Ok(Envelope { request: line.parse()?, proof: None });
+    };
+    let proof = match proof.split_whitespace().collect::<Vec<_>>().as_slice() {
+        [time, nonce, mac] => Proof {
+            time: time.parse().map_err(|error| format!("invalid time \"{}\": {}", time, error))?,
+            nonce: nonce.to_string(),
+            mac: mac.to_string(),
+        },
+        _ => return Err(format!("invalid proof \"{}\"", proof)),
+    };
+    Ok(Envelope { request: request.parse()?, proof: Some(proof) })
+}
+
+// Moving the file away first keeps requests appended while reading for the next poll
+pub fn take() -> Vec<Envelope> {
+    let Ok(path) = path() else {
+        return Vec::new();
+    };
+    let taken = path.with_extension("taken");
+    if fs::rename(&path, &taken).is_err() {
+        return Vec::new();
+    }
+    let contents = if is_stale(&taken) {String::new()} else {fs::read_to_string(&taken).unwrap_or_default()};
+    let _ = fs::remove_file(&taken);
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| parse(line).map_err(|error| eprintln!("Ignoring request: {}", error)).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn proof(request: Request, hash: &str, time: u64) -> Proof {
+        Proof::sign_at(request, hash, time, String::from("00112233445566778899aabbccddeeff"))
+    }
+
+    #[test]
+    fn requests_survive_a_round_trip() {
+        let sleep = SleepSettings { minutes: 45, fade_minutes: 15, floor: 10, after: AfterSleep::Pause };
+        let requests = [Request::Sleep(sleep), Request::CancelSleep, Request::Lock, Request::Override(80, 30), Request::CancelOverride];
+        for request in requests {
+            assert_eq!(request.to_string().parse(), Ok(request));
+        }
+        assert_eq!(Request::Sleep(sleep).to_string(), "sleep 45 15 10 pause");
+    }
+
+    #[test]
+    fn malformed_requests_are_refused() {
+        assert!("".parse::<Request>().is_err());
+        assert!("unlock".parse::<Request>().is_err());
+        assert!("override 80".parse::<Request>().is_err());
+        assert!("override 300 30".parse::<Request>().is_err());
+        assert!("sleep 45 15 10 shout".parse::<Request>().is_err());
+        assert_eq!("  lock ".parse(), Ok(Request::Lock));
+    }
+
+    #[test]
+    fn proofs_verify_only_for_the_signed_request_and_hash() {
+        let hash = pin::hash("1234").unwrap();
+        let proof = proof(Request::CancelOverride, &hash, NOW);
+        assert!(proof.verify(Request::CancelOverride, &hash, NOW));
+        assert!(!proof.verify(Request::CancelSleep, &hash, NOW));
+        assert!(!proof.verify(Request::CancelOverride, &pin::hash("1234").unwrap(), NOW));
+        let tampered = Proof { nonce: String::from("ffeeddccbbaa99887766554433221100"), ..proof };
+        assert!(!tampered.verify(Request::CancelOverride, &hash, NOW));
+    }
+
+    #[test]
+    fn old_and_future_proofs_are_refused() {
+        let hash = pin::hash("1234").unwrap();
+        let request = Request::Override(80, 30);
+        assert!(proof(request, &hash, NOW - 60).verify(request, &hash, NOW));
+        assert!(!proof(request, &hash, NOW - 61).verify(request, &hash, NOW));
+        assert!(proof(request, &hash, NOW + CLOCK_SKEW).verify(request, &hash, NOW));
+        assert!(!proof(request, &hash, NOW + CLOCK_SKEW + 1).verify(request, &hash, NOW));
+    }
+
+    #[test]
+    fn each_nonce_is_accepted_once_while_it_could_verify() {
+        let hash = pin::hash("1234").unwrap();
+        let proof = proof(Request::CancelSleep, &hash, NOW);
+        let mut replays = Replays::default();
+        assert!(replays.first_use(&proof, NOW));
+        assert!(!replays.first_use(&proof, NOW + 30));
+        let later = Proof::sign_at(Request::CancelSleep, &hash, NOW + 30, String::from("ffeeddccbbaa99887766554433221100"));
+        assert!(replays.first_use(&later, NOW + 30));
+        // Forgotten once the proof itself has gone stale
+        assert!(replays.first_use(&proof, NOW + 66));
+        assert!(!replays.first_use(&later, NOW + 66));
+    }
+
+    #[test]
+    fn lines_carry_the_proof_but_not_the_pin() {
+        let hash = pin::hash("1234").unwrap();
+        let proof = proof(Request::CancelOverride, &hash, NOW);
+        let line = line(Request::CancelOverride, Some(&proof));
+        assert!(line.starts_with(&format!("cancel-override\t{} 0011", NOW)));
+        assert_eq!(parse(&line), Ok(Envelope { request: Request::CancelOverride, proof: Some(proof) }));
+        assert_eq!(parse("lock"), Ok(Envelope { request: Request::Lock, proof: None }));
+        assert!(parse("cancel-override\t1234").is_err());
+    }
+}
@@ -1,13 +1,34 @@
 use std::{sync::mpsc::{Receiver, Sender}, thread::{self, JoinHandle}, time::Duration};
 
+pub mod audit;
+pub mod cli;
 pub mod components;
+pub mod config;
+pub mod export;
+pub mod ipc;
+pub mod pin;
 pub mod styles;
+#[cfg(test)]
+mod test_util;
+#[cfg(target_os = "linux")]
+pub mod tray;
 pub mod vol_ctl;
+pub mod volume;
+
+use vol_ctl::backend::{self, Channel, Direction};
+pub use volume::Volume;
 
 pub enum VolumeCommand {
-    GetVol(Option<f32>),
-    SetVol(Option<f32>),
+    GetVol(Option<Volume>),
+    SetVol(Option<Volume>),
     GetDevices(Option<Vec<String>>),
+    GetDefaultDevice(Option<String>),
+    GetInputVol(Option<Volume>),
+    SetInputVol(Option<Volume>),
+    GetInputDevices(Option<Vec<String>>),
+    GetDefaultInputDevice(Option<String>),
+    GetChannels(Option<Vec<Channel>>),
+    SetChannels(Option<Vec<Volume>>),
     GetMute(Option<bool>),
     SetMute(Option<bool>),
     Failed,
@@ -20,15 +41,39 @@ pub fn command_handler(tx: Sender<VolumeCommand>, rx: Receiver<VolumeCommand>) -
             if let Ok(command) = rx.try_recv() {
                 match command {
                     VolumeCommand::GetVol(_ignore) => {
-                        tx.send(VolumeCommand::GetVol(Some(cpvc::get_system_volume() as f32 / 100.0))).unwrap();
+                        tx.send(VolumeCommand::GetVol(Some(Volume::from(cpvc::get_system_volume())))).unwrap();
                     },
                     VolumeCommand::SetVol(vol) => {
-                        cpvc::set_system_volume((vol.unwrap() * 100.0) as u8);
+                        cpvc::set_system_volume(vol.unwrap().percent_u8());
                         tx.send(VolumeCommand::SetVol(vol)).unwrap();
                     },
                     VolumeCommand::GetDevices(_ignore) => {
                         tx.send(VolumeCommand::GetDevices(Some(cpvc::get_sound_devices()))).unwrap();
                     },
+                    VolumeCommand::GetDefaultDevice(_ignore) => {
+                        tx.send(VolumeCommand::GetDefaultDevice(Some(cpvc::get_default_output_dev()))).unwrap();
+                    },
+                    // cpvc has no input support, so these answer None where the mixer backend can't help either
+                    VolumeCommand::GetInputVol(_ignore) => {
+                        tx.send(VolumeCommand::GetInputVol(backend::volume(Direction::Input).ok())).unwrap();
+                    },
+                    VolumeCommand::SetInputVol(vol) => {
+                        let result = vol.and_then(|vol| backend::set_volume(Direction::Input, vol).ok().map(|_| vol));
+                        tx.send(VolumeCommand::SetInputVol(result)).unwrap();
+                    },
+                    VolumeCommand::GetInputDevices(_ignore) => {
+                        tx.send(VolumeCommand::GetInputDevices(backend::devices(Direction::Input).ok())).unwrap();
+                    },
+                    VolumeCommand::GetDefaultInputDevice(_ignore) => {
+                        tx.send(VolumeCommand::GetDefaultInputDevice(backend::default_device(Direction::Input).ok())).unwrap();
+                    },
+                    VolumeCommand::GetChannels(_ignore) => {
+                        tx.send(VolumeCommand::GetChannels(backend::channels(Direction::Output).ok())).unwrap();
+                    },
+                    VolumeCommand::SetChannels(volumes) => {
+                        let result = volumes.and_then(|volumes| backend::set_channels(Direction::Output, &volumes).ok().map(|_| volumes));
+                        tx.send(VolumeCommand::SetChannels(result)).unwrap();
+                    },
                     // For cpvc v0.5.0 update (transition in progress)
                     VolumeCommand::GetMute(_ignore) => {
                         if cpvc::get_system_volume() == 0 {
@@ -1,24 +1,33 @@
-use std::{sync::{mpsc::{self, Receiver, Sender}, Arc, Mutex}, thread::{self, JoinHandle}, time::Duration};
-use iced::{widget::{button, pick_list, radio, slider, text, text_input, toggler, vertical_space, Column, Row}, Alignment, Element, Length, Size, Subscription, Task, Theme};
-use vol_limiter::{VolumeCommand, command_handler, styles::get_rgb_color};
+use std::{fmt::{self, Display}, ops::RangeInclusive, path::PathBuf, sync::{mpsc::{self, Receiver, Sender}, Arc, Mutex}, thread::{self, JoinHandle}, time::{Duration, Instant, SystemTime}};
+use chrono::{Datelike, NaiveDateTime, Weekday};
+use iced::{keyboard, window, widget::{button, canvas, checkbox, focus_next, focus_previous, pick_list, progress_bar, radio, scrollable, slider, text, text_input, toggler, Column, Row}, Alignment, Element, Length, Pixels, Settings as AppSettings, Size, Subscription, Task, Theme};
+use vol_limiter::{Volume, VolumeCommand, audit::{self, Cause, Entry, Event, Kind}, cli, export::{Export, Format, Range}, command_handler, config::{LevelSettings, Settings}, ipc::{self, Request}, pin, styles::{self, buttons, inputs, sliders, theme_file::{self, CustomTheme}, ThemeChoice}};
 // use cpvc::command::{get_sound_devices_command, get_system_volume_command, set_system_volume_command};
-use cpvc::{get_sound_devices, get_system_volume, set_system_volume};
-use vol_limiter::{components::hov_container_row::{self, HovContainer}};
+use cpvc::get_sound_devices;
+use vol_limiter::components::{history_graph::HistoryGraph, hov_container_row::{self, HovContainer}, numeric_input::{self, NumericInput}};
+use vol_limiter::vol_ctl::{backend::{Channel, Direction}, balance, curve::Curve, device::{self, DeviceClass, SensitivityProfile}, dosimeter::{DoseStandard, Dosimeter}, engine::{Engine, Limits}, history::{History, Span}, media, notify, overrides::Override, ramp::Ramp, schedule::{self, Clock, Rule, RuleAction, Schedule, SystemClock}, sleep_timer::{AfterSleep, SleepSettings, SleepTimer}, tamper::{TamperMonitor, TamperSettings}, volumes::{DeviceVolumes, VolumeControls}};
+
+const DOSE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// Longer gaps between samples mean the app was suspended, not that audio kept playing
+const MAX_DOSE_SAMPLE: Duration = Duration::from_secs(5);
 
 // Issue: Vol-limiter won't let you choose custom vol limit if it is = to 20, 50, or 80 because of the auto selector feature
 #[derive(Debug, Clone)]
 enum Message {
-    EnableLimit,
-    DisableLimit,
+    // None when the app toggles the limiter around its own changes, those stay out of the audit log
+    EnableLimit(Option<Cause>),
+    DisableLimit(Option<Cause>),
     ChangePercent(String, bool),
     ConfirmPercent(bool, bool),
     ChangeDevice(String),
     UpdateDeviceList,
     AutoLimiter,
     AutoCheck(bool),
-    ChangeByOne(bool, bool),
+    SubmitPercent(u8, bool),
     SystemVolChange,
     SliderVolChange(u8, bool),
+    // Limit changes the app makes itself (schedule rules, re-applying around the limiter), never from a control
+    SetLimit(u8),
     None,
     ChangeVolInput(InputType),
     ChangeLimitSel(BuiltIn),
@@ -27,16 +36,340 @@ enum Message {
     ChangeAutoAutoLimiter(bool),
     OnToggle(bool),
     OnPick(String),
+    FocusNext(bool),
+    ChangeTheme(ThemeChoice),
+    DetectSystemTheme,
+    SystemThemeChanged(Theme),
+    ReloadThemes,
+    ThemesChanged(Vec<CustomTheme>, Vec<(PathBuf, Option<SystemTime>)>),
+    ChangeDoseStandard(DoseStandard),
+    ToggleDoseTighten(bool),
+    ChangeSensitivity(String),
+    SubmitSensitivity(u8),
+    ToggleSchedule(bool),
+    ScheduleTick,
+    AddRule,
+    AddQuietHours,
+    EditRule(usize, RuleEdit),
+    EditRamp(RampEdit),
+    StartSleep(SleepSettings),
+    CancelSleep,
+    SleepTick,
+    EditSleep(SleepEdit),
+    PollRequests,
+    ToggleSlew(bool),
+    ChangeSlew(String),
+    SubmitSlew(u8),
+    ToggleFloor(bool),
+    ChangeFloor(String),
+    SubmitFloor(u8),
+    ToggleAllowMute(bool),
+    ToggleLock(bool),
+    ChangeLock(String),
+    SubmitLock(u8),
+    CheckOutputDevice,
+    ToggleSafeVolume(bool),
+    ChangeSafeVolume(String),
+    SubmitSafeVolume(u8),
+    ToggleStartupVolume(bool),
+    ChangeStartupVolume(String),
+    SubmitStartupVolume(u8),
+    ToggleInputLimit(InputLimit, bool),
+    ChangeInputLimit(InputLimit, String),
+    SubmitInputLimit(InputLimit, u8),
+    InputVolChange(u8),
+    ChangeMaxVolume(String),
+    SubmitMaxVolume(u8),
+    BalanceChange(i16),
+    ToggleDb(bool),
+    ChangeCurve(Curve),
+    ChangePin(String),
+    ChangeNewPin(String),
+    Unlock,
+    Lock,
+    SetPin,
+    RemovePin,
+    CloseRequested(window::Id),
+    EditOverride(OverrideEdit),
+    GrantOverride(u8, u8),
+    EndOverride,
+    OverrideTick,
+    EditTamper(TamperEdit),
+    SelectTab(Tab),
+    FilterAudit(AuditFilter),
+    SearchAudit(String),
+    RefreshAudit,
+    SelectSpan(Span),
+    EditExport(ExportEdit),
+    Export,
+    // What the export wrote, or why it failed
+    Exported(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Controls,
+    Audit,
+    Stats,
+}
+
+// The viewer only shows this many of the latest audit entries
+const AUDIT_ROWS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AuditFilter {
+    All,
+    Only(Kind),
+}
+
+impl AuditFilter {
+    fn all() -> Vec<AuditFilter> {
+        std::iter::once(AuditFilter::All).chain(Kind::ALL.map(AuditFilter::Only)).collect()
+    }
+
+    fn matches(self, kind: Kind) -> bool {
+        match self {
+            AuditFilter::All => true,
+            AuditFilter::Only(only) => only == kind,
+        }
+    }
+}
+
+impl Display for AuditFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditFilter::All => write!(f, "All events"),
+            AuditFilter::Only(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+// Only PulseAudio and PipeWire let the output go past 100%
+const AMPLIFICATION_POSSIBLE: bool = cfg!(target_os = "linux");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputLimit {
+    Ceiling,
+    Floor,
+    Lock,
+}
+
+impl InputLimit {
+    const ALL: [InputLimit; 3] = [InputLimit::Ceiling, InputLimit::Floor, InputLimit::Lock];
+
+    fn label(self) -> &'static str {
+        match self {
+            InputLimit::Ceiling => "Never above",
+            InputLimit::Floor => "Never below",
+            InputLimit::Lock => "Lock gain at",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+const DEFAULT_SAFE_VOLUME: u8 = 30;
+
+#[derive(Debug, Clone)]
+enum SleepEdit {
+    Minutes(String),
+    SubmitMinutes(u8),
+    Fade(String),
+    SubmitFade(u8),
+    Floor(String),
+    SubmitFloor(u8),
+    After(AfterSleep),
+}
+
+#[derive(Debug, Clone)]
+enum RampEdit {
+    Enabled(bool),
+    Start(String),
+    End(String),
+    Until(String),
+    From(String),
+    SubmitFrom(u8),
+    To(String),
+    SubmitTo(u8),
+}
+
+#[derive(Debug, Clone)]
+enum RuleEdit {
+    Name(String),
+    SubmitName,
+    ToggleDay(Weekday),
+    Start(String),
+    End(String),
+    Limited(bool),
+    Percent(String),
+    SubmitPercent(u8),
+    Device(String),
+    Remove,
+}
+
+const ANY_DEVICE: &str = "Any device";
+
+// Text of the inputs of a schedule rule while it's being edited
+#[derive(Debug, Clone)]
+struct RuleDraft {
+    start: String,
+    end: String,
+    percent: String,
+}
+
+#[derive(Debug, Clone)]
+enum TamperEdit {
+    Enabled(bool),
+    Threshold(String),
+    SubmitThreshold(u8),
+    Window(String),
+    SubmitWindow(u8),
+    Notify(bool),
+    Mute(bool),
+    MuteMinutes(String),
+    SubmitMuteMinutes(u8),
+    Lower(bool),
+    LowerBy(String),
+    SubmitLowerBy(u8),
+}
+
+#[derive(Debug, Clone)]
+struct TamperDraft {
+    threshold: String,
+    window: String,
+    mute_minutes: String,
+    lower_by: String,
+}
+
+impl TamperDraft {
+    fn new(tamper: &TamperSettings) -> Self {
+        Self {
+            threshold: tamper.threshold.to_string(),
+            window: tamper.window_minutes.to_string(),
+            mute_minutes: tamper.mute_minutes.to_string(),
+            lower_by: tamper.lower_by.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum OverrideEdit {
+    Percent(String),
+    SubmitPercent(u8),
+    Minutes(String),
+    SubmitMinutes(u8),
+}
+
+// What the GUI offers to grant next, not kept between runs
+#[derive(Debug, Clone)]
+struct OverrideDraft {
+    percent: u8,
+    minutes: u8,
+    percent_str: String,
+    minutes_str: String,
+}
+
+impl Default for OverrideDraft {
+    fn default() -> Self {
+        Self {
+            percent: 70,
+            minutes: 30,
+            percent_str: 70.to_string(),
+            minutes_str: 30.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ExportEdit {
+    Format(Format),
+    From(String),
+    To(String),
+    Dir(String),
+}
+
+#[derive(Debug, Clone)]
+struct ExportDraft {
+    format: Format,
+    from: String,
+    to: String,
+    dir: String,
+    message: Option<String>,
+}
+
+impl ExportDraft {
+    fn new(range: Range) -> Self {
+        Self {
+            format: Format::default(),
+            from: range.from.to_string(),
+            to: range.to.to_string(),
+            dir: dirs::download_dir().or_else(dirs::home_dir).map(|dir| dir.display().to_string()).unwrap_or_default(),
+            message: None,
+        }
+    }
+
+    fn range(&self) -> Result<Range, String> {
+        Range::new(Range::parse_date(&self.from)?, Range::parse_date(&self.to)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SleepDraft {
+    minutes: String,
+    fade: String,
+    floor: String,
+}
+
+impl SleepDraft {
+    fn new(sleep: &SleepSettings) -> Self {
+        Self {
+            minutes: sleep.minutes.to_string(),
+            fade: sleep.fade_minutes.to_string(),
+            floor: sleep.floor.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RampDraft {
+    start: String,
+    end: String,
+    until: String,
+    from: String,
+    to: String,
+}
+
+impl RampDraft {
+    fn new(ramp: &Ramp) -> Self {
+        Self {
+            start: schedule::format_time(ramp.start),
+            end: schedule::format_time(ramp.end),
+            until: schedule::format_time(ramp.until),
+            from: ramp.from.to_string(),
+            to: ramp.to.to_string(),
+        }
+    }
+}
+
+impl RuleDraft {
+    fn new(rule: &Rule) -> Self {
+        Self {
+            start: schedule::format_time(rule.start),
+            end: schedule::format_time(rule.end),
+            percent: match rule.action {
+                RuleAction::Limit(percent) => percent.to_string(),
+                RuleAction::Unlimited => 100.to_string(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum Error {
     UpdateError,
-    ParseError,
-    ParseVolError,
     AdjustWhileOn,
-
-
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -53,6 +386,17 @@ enum BuiltIn {
     Custom
 }
 
+impl BuiltIn {
+    fn from_percent(percent: u8) -> Self {
+        match percent {
+            20 => BuiltIn::Twenty,
+            50 => BuiltIn::Fifty,
+            80 => BuiltIn::Eighty,
+            _ => BuiltIn::Custom,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct VolControl {
     limiter: bool,
@@ -61,10 +405,9 @@ struct VolControl {
     all_devices: Vec<String>,
     devices: Vec<String>,
     device: Option<String>,
-    runner: Option<JoinHandle<()>>,
+    engine: Option<Engine>,
     scanner: Option<JoinHandle<()>>,
     autocheck: bool,
-    tx_limiter: Option<Sender<bool>>,
     tx_scanner: Option<Sender<()>>,
     mutex: Arc<Mutex<Vec<String>>>,
     thread_count: Arc<Mutex<i32>>,
@@ -74,10 +417,69 @@ struct VolControl {
     auto_autolimiter: bool,
     input_vol: Option<InputType>,
     sel_lim: Option<BuiltIn>,
-    volume: u8, 
-    vol_str: String,
+    volumes: DeviceVolumes,
     cmd_tx: Sender<VolumeCommand>,
     cmd_rx: Receiver<VolumeCommand>,
+    theme_choice: ThemeChoice,
+    system_theme: Theme,
+    settings: Settings,
+    custom_themes: Vec<CustomTheme>,
+    theme_stamps: Vec<(PathBuf, Option<SystemTime>)>,
+    dosimeter: Dosimeter,
+    dose_sampled: Option<Instant>,
+    dose_saved: Instant,
+    output_device: String,
+    sensitivity_str: String,
+    limits: Arc<Limits>,
+    clock: Arc<dyn Clock>,
+    active_rule: Option<Rule>,
+    rule_drafts: Vec<RuleDraft>,
+    ramp_draft: RampDraft,
+    sleep_timer: Option<SleepTimer>,
+    sleep_draft: SleepDraft,
+    slew_str: String,
+    floor_str: String,
+    lock_str: String,
+    safe_str: String,
+    startup_str: String,
+    input_limits: Arc<Limits>,
+    input_engine: Option<Engine>,
+    // None while the platform gives us no control over capture devices
+    input_device: Option<String>,
+    input_volume: Option<u8>,
+    input_strs: [String; 3],
+    max_volume_str: String,
+    over_amplified: Option<(u8, NaiveDateTime)>,
+    channels: Vec<Channel>,
+    // None when the output has no left/right pair to balance
+    balance: Option<i16>,
+    // Protected settings refuse changes until the PIN is entered
+    locked: bool,
+    pin_str: String,
+    new_pin_str: String,
+    lock_message: Option<String>,
+    limit_override: Option<Override>,
+    override_draft: OverrideDraft,
+    tamper: TamperMonitor,
+    tamper_draft: TamperDraft,
+    // Set when an escalation mutes the output, the ceiling stays at 0 until then
+    muted_until: Option<NaiveDateTime>,
+    tamper_message: Option<String>,
+    // Kept out of the settings, see pin::load_hash
+    pin_hash: Option<String>,
+    replays: ipc::Replays,
+    // Why config.toml couldn't be loaded, it isn't written over while set
+    settings_error: Option<String>,
+    // Settings as last written, changes are logged against it
+    saved_settings: Settings,
+    tab: Tab,
+    audit_entries: Vec<Entry>,
+    audit_filter: AuditFilter,
+    audit_search: String,
+    history: History,
+    history_sampled: Option<Instant>,
+    stats_span: Span,
+    export_draft: ExportDraft,
 }
 
 // Do not use, cannot provide cmd_tx, cmd_rx
@@ -94,10 +496,9 @@ impl Default for VolControl {
             all_devices: device_list.clone(),
             devices: device_list,
             device: Some(String::new()),
-            runner: None,
+            engine: None,
             scanner: None,
             autocheck: false,
-            tx_limiter: None,
             tx_scanner: None,
             mutex: Arc::new(Mutex::new(copy)),
             thread_count: Arc::new(Mutex::new(0)),
@@ -107,16 +508,76 @@ impl Default for VolControl {
             auto_autolimiter: true,
             input_vol: Some(InputType::Slider),
             sel_lim: Some(BuiltIn::Twenty),
-            volume: 0,
-            vol_str: 0.to_string(),
+            volumes: DeviceVolumes::default(),
             cmd_tx: tx,
             cmd_rx: rx,
+            theme_choice: ThemeChoice::default(),
+            system_theme: Theme::Dark,
+            settings: Settings::default(),
+            custom_themes: Vec::new(),
+            theme_stamps: Vec::new(),
+            dosimeter: Dosimeter::new(DoseStandard::default(), SystemClock.now().date()),
+            dose_sampled: None,
+            dose_saved: Instant::now(),
+            output_device: String::new(),
+            sensitivity_str: String::new(),
+            limits: Arc::new(Limits::default()),
+            clock: Arc::new(SystemClock),
+            active_rule: None,
+            rule_drafts: Vec::new(),
+            ramp_draft: RampDraft::new(&Ramp::default()),
+            sleep_timer: None,
+            sleep_draft: SleepDraft::new(&SleepSettings::default()),
+            slew_str: String::new(),
+            floor_str: String::new(),
+            lock_str: String::new(),
+            safe_str: String::new(),
+            startup_str: String::new(),
+            input_limits: Arc::new(Limits::default()),
+            input_engine: None,
+            input_device: None,
+            input_volume: None,
+            input_strs: Default::default(),
+            max_volume_str: String::new(),
+            over_amplified: None,
+            channels: Vec::new(),
+            balance: None,
+            locked: false,
+            pin_str: String::new(),
+            new_pin_str: String::new(),
+            lock_message: None,
+            limit_override: None,
+            override_draft: OverrideDraft::default(),
+            tamper: TamperMonitor::default(),
+            tamper_draft: TamperDraft::new(&TamperSettings::default()),
+            muted_until: None,
+            tamper_message: None,
+            pin_hash: None,
+            replays: ipc::Replays::default(),
+            settings_error: None,
+            saved_settings: Settings::default(),
+            tab: Tab::Controls,
+            audit_entries: Vec::new(),
+            audit_filter: AuditFilter::All,
+            audit_search: String::new(),
+            history: History::default(),
+            history_sampled: None,
+            stats_span: Span::default(),
+            export_draft: ExportDraft::new(Range::last_week(SystemClock.now().date())),
         }
     }
 }
 
 impl VolControl {
-    pub fn new(mut cmd_tx: Sender<VolumeCommand>, mut cmd_rx: Receiver<VolumeCommand>) -> Self {
+    pub fn new(mut cmd_tx: Sender<VolumeCommand>, mut cmd_rx: Receiver<VolumeCommand>, loaded: Result<Settings, String>) -> Self {
+        let (settings, settings_error) = match loaded {
+            Ok(settings) => (settings, None),
+            Err(error) => (Settings::default(), Some(error)),
+        };
+        let pin_hash = pin::load_hash();
+        let limit_override = Override::load(SystemClock.now());
+        let limiter = limiter_at_start(&settings, limit_override, pin_hash.is_some(), settings_error.is_none() && Settings::exists());
+        let auto_autolimiter = auto_limiter_at_start(limiter, pin_hash.is_some());
         let device_list = {
             if let VolumeCommand::GetDevices(Some(devices)) = VolControl::send_command_with_tx_rx(&mut cmd_tx, &mut cmd_rx, VolumeCommand::GetDevices(None)) {
                 devices
@@ -126,81 +587,180 @@ impl VolControl {
         };
         let curr_vol = {
             if let VolumeCommand::GetVol(Some(vol)) = VolControl::send_command_with_tx_rx(&mut cmd_tx, &mut cmd_rx, VolumeCommand::GetVol(None)) { 
-                (vol * 100.0) as u8
+                vol.percent_u8()
             } else {
                 0
             }
         };
+        let output_device = {
+            if let VolumeCommand::GetDefaultDevice(Some(device)) = VolControl::send_command_with_tx_rx(&mut cmd_tx, &mut cmd_rx, VolumeCommand::GetDefaultDevice(None)) {
+                device
+            } else {
+                String::new()
+            }
+        };
         let copy = device_list.clone();
-        Self { 
-            limiter: false, 
-            percent: 20, 
-            percent_str: 20.to_string(),
+        let mut control = Self { 
+            limiter,
+            percent: settings.limit.percent,
+            percent_str: settings.limit.percent.to_string(),
             all_devices: device_list.clone(),
             devices: device_list,
             device: Some(String::new()),
-            runner: None,
+            engine: None,
             scanner: None,
             autocheck: false,
-            tx_limiter: None,
             tx_scanner: None,
             mutex: Arc::new(Mutex::new(copy)),
             thread_count: Arc::new(Mutex::new(0)),
             error: None,
             error_length: 0,
             autolimiter: true,
-            auto_autolimiter: true,
+            auto_autolimiter,
             input_vol: Some(InputType::Slider),
-            sel_lim: Some(BuiltIn::Twenty),
-            volume: curr_vol,
-            vol_str: curr_vol.to_string(),
+            sel_lim: Some(BuiltIn::from_percent(settings.limit.percent)),
+            volumes: DeviceVolumes::load(),
             cmd_tx,
             cmd_rx,
+            theme_choice: ThemeChoice::from_name(&settings.theme),
+            // Detected off the UI thread once the app is running
+            system_theme: Theme::Dark,
+            dosimeter: Dosimeter::load(settings.dosimeter.standard, SystemClock.now().date()),
+            rule_drafts: settings.schedule.rules.iter().map(RuleDraft::new).collect(),
+            ramp_draft: RampDraft::new(&settings.ramp),
+            sleep_timer: None,
+            sleep_draft: SleepDraft::new(&settings.sleep),
+            slew_str: settings.slew.rate.to_string(),
+            floor_str: settings.floor.percent.to_string(),
+            lock_str: settings.lock.percent.to_string(),
+            safe_str: DEFAULT_SAFE_VOLUME.to_string(),
+            startup_str: settings.startup_volume.percent.to_string(),
+            input_limits: Arc::new(Limits::default()),
+            input_engine: None,
+            input_device: None,
+            input_volume: None,
+            input_strs: InputLimit::ALL.map(|limit| input_level(&settings, limit).percent.to_string()),
+            max_volume_str: settings.max_volume.to_string(),
+            over_amplified: None,
+            channels: Vec::new(),
+            balance: None,
+            locked: pin_hash.is_some(),
+            pin_hash,
+            replays: ipc::Replays::default(),
+            settings_error,
+            pin_str: String::new(),
+            new_pin_str: String::new(),
+            lock_message: None,
+            limit_override,
+            override_draft: OverrideDraft::default(),
+            tamper: TamperMonitor::default(),
+            tamper_draft: TamperDraft::new(&settings.tamper),
+            muted_until: None,
+            tamper_message: None,
+            saved_settings: settings.clone(),
+            tab: Tab::Controls,
+            audit_entries: Vec::new(),
+            audit_filter: AuditFilter::All,
+            audit_search: String::new(),
+            history: History::load(SystemClock.now()),
+            history_sampled: None,
+            stats_span: Span::default(),
+            export_draft: ExportDraft::new(Range::last_week(SystemClock.now().date())),
+            settings,
+            custom_themes: theme_file::load_themes(),
+            theme_stamps: theme_file::scan(),
+            dose_sampled: None,
+            dose_saved: Instant::now(),
+            output_device: output_device.clone(),
+            sensitivity_str: String::new(),
+            limits: Arc::new(Limits::default()),
+            clock: Arc::new(SystemClock),
+            active_rule: None,
+        };
+        control.sensitivity_str = control.sensitivity().max_spl_db.round().to_string();
+        control.volumes.get_mut(&output_device).show(curr_vol);
+        control.refresh_input();
+        control.sync_engine();
+        control.enter_device();
+        if let Some(volume) = control.settings.startup_volume.level() {
+            control.set_volume(control.capped(volume));
         }
+        control
     }
 }
 
 impl VolControl {
-    pub fn update(&mut self, message:Message) -> Task<Message> {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
+        // Only the user can turn the limiter off while locked, the auto limiter may still turn it on
+        if self.locked && matches!(message, Message::DisableLimit(Some(Cause::AutoLimiter))) {
+            return Task::none();
+        }
+        if self.locked && is_protected(&message) {
+            self.lock_message = Some(String::from("Locked, enter the PIN to change this"));
+            return Task::none();
+        }
+        if self.locked && self.raises_limit(&message) {
+            self.lock_message = Some(String::from("Locked, the limit can only be lowered without the PIN"));
+            // Drops the typed value too, EnableLimit would otherwise keep asking to confirm it
+            self.percent_str = self.percent.to_string();
+            return Task::none();
+        }
+        self.handle(message)
+    }
+
+    // Follows whether the picked device is present, unless a schedule rule decides or a temporary limit is granted,
+    // which is only enforced while the limiter runs
+    fn auto_limiter(&mut self) -> Option<Message> {
+        if !self.autolimiter || !self.auto_autolimiter || self.active_rule.is_some() || self.limit_override.is_some() {
+            return None;
+        }
+        let present = self.device.as_ref().is_some_and(|device| self.devices.contains(device));
+        if present && self.device != Some(String::new()) && !self.limiter {
+            Some(Message::EnableLimit(Some(Cause::AutoLimiter)))
+        } else if (!present || self.device.is_none() && self.limiter) && !self.locked {
+            self.device = Some(String::new());
+            Some(Message::DisableLimit(Some(Cause::AutoLimiter)))
+        } else {
+            None
+        }
+    }
+
+    // Whether a control asks for a higher limit than the current one
+    fn raises_limit(&self, message: &Message) -> bool {
+        match message {
+            Message::SubmitPercent(percent, true) | Message::SliderVolChange(percent, true) => *percent > self.percent,
+            Message::ConfirmPercent(true, _) => numeric_input::parse(&self.percent_str, &(0..=100), true).is_ok_and(|percent| percent > self.percent),
+            _ => false,
+        }
+    }
+
+    fn handle(&mut self, message:Message) -> Task<Message> {
         match message {
-            Message::EnableLimit => {
-                    println!("limiter {:?} runner{:?}", self.tx_limiter, self.runner);
-                    if self.tx_limiter.is_none() && self.runner.is_none() {
-                        // if self.tx.is_none() && self.rx.is_none() {
-                        //     let (tx, rx) = mpsc::channel();
-                        //     self.tx = Some(tx.clone());
-                        //     self.rx.replace(rx);
-                        // } 
+            Message::EnableLimit(cause) => {
+                    if !self.limiter {
                         if self.percent.to_string() == self.percent_str {
-                            let percent = self.percent;
-                            let (tx, rx) = mpsc::channel();
-                            self.tx_limiter = Some(tx.clone());
-                            println!("Enabling");
                             self.limiter = true;
-                            tx.send(true).unwrap();
-                            self.runner.replace(enable_limiter(percent, rx));
+                            self.log_limiter(cause);
+                            self.save_limit();
+                            self.sync_engine();
+                            let ceiling = self.ceiling();
                             let volume = if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) { 
-                                (vol * 100.0) as u8
+                                vol.percent_u8()
                             } else {
                                 0
                             };
-                            self.volume = if volume < self.percent {
-                                volume
-                            } else {
-                                self.percent
-                            };
-                            self.vol_str = self.volume.to_string();
+                            self.controls().show(volume.min(ceiling));
                             Task::none()
                         } else {
-                            self.volume = if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) { 
-                                (vol * 100.0) as u8
+                            let volume = if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) { 
+                                vol.percent_u8()
                             } else {
                                 0
                             };
-                            self.vol_str = self.volume.to_string();
+                            self.controls().show(volume);
                             Task::batch(vec![
                                 Task::perform(async {}, |_| Message::ConfirmPercent(true, false)),
-                                Task::perform(async {}, |_| Message::EnableLimit),
+                                Task::perform(async {}, move |_| Message::EnableLimit(cause)),
                             ])
                         }
                         
@@ -209,82 +769,40 @@ impl VolControl {
                         Task::none()
                     }
             },
-            Message::DisableLimit => {
-                println!("Diabling {:?}", self.tx_limiter);
-                if let Some(tx) = self.tx_limiter.take() {
+            Message::DisableLimit(cause) => {
+                if self.limiter {
                     self.limiter = false;
-                    disable_limiter(    tx.clone());
-                    let run = self.runner.take();
-                    match run {
-                        Some(thread) => {
-                            let _ = thread.join().map_err(|error| eprintln!("Error: {:?}", error));
-                        }
-                        None => {}
-                    };
-                }   
-                Task::none()             
+                    self.log_limiter(cause);
+                    self.save_limit();
+                    self.sync_engine();
+                }
+                Task::none()
             },
             Message::ChangePercent(input, limit)=> {
                 if limit {
                     self.percent_str = input;
-                    if self.percent_str.parse::<u8>().is_err()|| self.percent_str.parse::<u8>().unwrap_or(0) > 100 {
-                        self.error = Some(Error::ParseError);
-                        self.error_length = 0;
-                    } else {
-                        self.error = None;
-                    }
                 } else {
-                    self.vol_str = input;
-                    if self.vol_str.parse::<u8>().is_err()|| self.vol_str.parse::<u8>().unwrap_or(0) > 100 {
-                        self.error = Some(Error::ParseVolError);
-                        self.error_length = 0;
-                    } else {
-                        self.error = None;
-                    }
+                    self.controls().vol_str = input;
                 }
                 Task::none()
             },
             Message::ConfirmPercent(limit, manual) => {
-                if limit && !self.limiter{
-                    self.percent = if let Ok(new) = self.percent_str.parse::<u8>() {if new <= 100 {new} else {100}} else {self.error = Some(Error::ParseError); self.error_length = 0; self.percent};
-                    self.percent_str = self.percent.to_string();
-                    if !manual {
-                        match self.percent {
-                            20 => {
-                                self.sel_lim.replace(BuiltIn::Twenty);
-                            },
-                            50 => {
-                                self.sel_lim.replace(BuiltIn::Fifty);
-                            },
-                            80 => {
-                                self.sel_lim.replace(BuiltIn::Eighty);
-                            },
-                            _ => {
-                                self.sel_lim.replace(BuiltIn::Custom);
-                            },
-                        }
-                    }
-                    Task::none()
-                } else if limit && self.limiter {
-                    Task::batch(vec![
-                        Task::perform(async {}, move |_| Message::DisableLimit),
-                        Task::perform(async {}, move |_| Message::ConfirmPercent(limit, false)),
-                        Task::perform(async {}, move |_| Message::EnableLimit),
-                    ])
+                let (input, current, range) = if limit {
+                    (self.percent_str.as_str(), self.percent, self.limit_range())
                 } else {
-                    if !self.limiter {
-                        self.volume = if let Ok(new) = self.vol_str.parse::<u8>() {if new <= 100 {new} else {100}} else {self.error = Some(Error::ParseError); self.error_length = 0; self.volume};
-                        self.vol_str = self.volume.to_string();
-                    } else {
-                        self.volume = if let Ok(new) = self.vol_str.parse::<u8>() {if new <= self.percent {new} else {self.percent}} else {self.error = Some(Error::ParseError); self.error_length = 0; self.volume};
-                        self.vol_str = self.volume.to_string();
-                    }
-                    self.send_command(VolumeCommand::SetVol(Some(self.volume as f32 * 100.0)));
-                    Task::none()
-                }
-            }
+                    (self.vol_str(), self.volume(), if self.limiter {0..=self.ceiling()} else {0..=100})
+                };
+                let percent = numeric_input::parse(input, &range, true).unwrap_or(current);
+                self.apply_percent(percent, limit, manual)
+            },
+            Message::SubmitPercent(percent, limit) => {
+                self.apply_percent(percent, limit, false)
+            },
+            Message::SetLimit(percent) => {
+                self.apply_percent(percent, true, false)
+            },
             Message::ChangeDevice(device) => {
-                if device == String::from("") {
+                if device.is_empty() {
                     self.device = None;
                 } else {
                     self.device = Some(device);
@@ -293,8 +811,6 @@ impl VolControl {
             },
             Message::UpdateDeviceList => {
                 if Arc::clone(&self.mutex).lock().unwrap().len() != self.devices.len() {
-                    println!("Length 1 = {:?} Length2 = {}", Arc::clone(&self.mutex).lock().unwrap(), self.devices.len());
-                    println!("DEVICE LIsT cHANGED!");
                     for device in Arc::clone(&self.mutex).lock().unwrap().iter() {
                         if !self.all_devices.contains(device) {
                             self.all_devices.push(String::from(device));
@@ -306,32 +822,277 @@ impl VolControl {
                         self.devices.clone()
                     };
                 }     
+                self.refresh_output_device();
                 Task::none()   
             }
-            Message::AutoLimiter => {
-                if self.autolimiter && self.auto_autolimiter {
-                    if self.devices.contains(self.device.as_ref().unwrap_or(&String::from(""))) && self.device != Some(String::from("")) && !self.limiter {
-                        let device = self.device.clone().unwrap_or(String::from(""));
-                        println!("{}", device);
-                        println!("Turning on!");
-                        Task::perform(async {}, |_| Message::EnableLimit)
-                    } else if !self.devices.contains(self.device.as_ref().unwrap_or(&String::from(""))) || self.device == None && self.limiter {
-                        self.device = Some(String::from(""));
-                        let device = self.device.clone().unwrap_or(String::from(""));
-                        println!("{}", device);
-                        println!("Turning OFF!");
-                        Task::perform(async {}, |_| Message::DisableLimit)
-                    } else {
-                        Task::none()
+            Message::CheckOutputDevice => {
+                self.refresh_output_device();
+                self.refresh_input();
+                self.refresh_channels();
+                Task::none()
+            },
+            Message::ToggleInputLimit(limit, enabled) => {
+                input_level_mut(&mut self.settings, limit).enabled = enabled;
+                self.update_limits();
+                Task::none()
+            },
+            Message::ChangeInputLimit(limit, input) => {
+                self.input_strs[limit.index()] = input;
+                Task::none()
+            },
+            Message::SubmitInputLimit(limit, percent) => {
+                input_level_mut(&mut self.settings, limit).percent = percent;
+                self.input_strs[limit.index()] = percent.to_string();
+                self.update_limits();
+                Task::none()
+            },
+            Message::ChangeMaxVolume(input) => {
+                self.max_volume_str = input;
+                Task::none()
+            },
+            Message::SubmitMaxVolume(max_volume) => {
+                self.settings.max_volume = max_volume;
+                self.max_volume_str = max_volume.to_string();
+                self.update_limits();
+                Task::none()
+            },
+            Message::BalanceChange(balance) => {
+                // The louder side stays at the current level, capped like the master volume
+                let mut volume = self.channels.iter().map(|channel| channel.volume).max().unwrap_or(Volume::from(self.volume()));
+                if self.limiter {
+                    volume = volume.min(Volume::from(self.ceiling()));
+                }
+                let volumes = balance::apply_balance(&self.channels, volume, balance);
+                if let VolumeCommand::SetChannels(Some(volumes)) = self.send_command(VolumeCommand::SetChannels(Some(volumes))) {
+                    for (channel, volume) in self.channels.iter_mut().zip(volumes) {
+                        channel.volume = volume;
                     }
+                    self.balance = Some(balance);
+                }
+                Task::none()
+            },
+            Message::ToggleDb(show) => {
+                self.settings.show_db = show;
+                self.save_settings();
+                Task::none()
+            },
+            Message::ChangeCurve(curve) => {
+                self.settings.curve = curve;
+                self.save_settings();
+                Task::none()
+            },
+            Message::ChangePin(input) => {
+                self.pin_str = input;
+                Task::none()
+            },
+            Message::ChangeNewPin(input) => {
+                self.new_pin_str = input;
+                Task::none()
+            },
+            Message::Unlock => {
+                if self.pin_matches(Some(&self.pin_str)) {
+                    self.locked = false;
+                    self.lock_message = None;
                 } else {
+                    self.lock_message = Some(String::from("Wrong PIN"));
+                }
+                self.pin_str.clear();
+                Task::none()
+            },
+            Message::Lock => {
+                self.locked = self.pin_hash.is_some();
+                self.lock_message = None;
+                Task::none()
+            },
+            Message::SetPin => {
+                match pin::hash(&self.new_pin_str) {
+                    Ok(hash) => match pin::save_hash(Some(&hash)) {
+                        Ok(()) => {
+                            self.pin_hash = Some(hash);
+                            self.audit(Event::Setting { name: String::from("pin"), value: String::from("(changed)") });
+                            self.lock_message = Some(String::from("PIN saved, lock the settings when you're done"));
+                        },
+                        Err(error) => self.lock_message = Some(format!("Failed to save the PIN: {}", error)),
+                    },
+                    Err(error) => self.lock_message = Some(error),
+                }
+                self.new_pin_str.clear();
+                Task::none()
+            },
+            Message::RemovePin => {
+                match pin::save_hash(None) {
+                    Ok(()) => {
+                        self.pin_hash = None;
+                        self.audit(Event::Setting { name: String::from("pin"), value: String::from("(removed)") });
+                        self.lock_message = None;
+                    },
+                    Err(error) => self.lock_message = Some(format!("Failed to remove the PIN: {}", error)),
+                }
+                Task::none()
+            },
+            Message::CloseRequested(id) => {
+                if self.locked {
+                    self.lock_message = Some(String::from("Locked, enter the PIN to quit"));
                     Task::none()
+                } else {
+                    self.save_typed();
+                    window::close(id)
+                }
+            },
+            Message::EditOverride(edit) => {
+                let draft = &mut self.override_draft;
+                match edit {
+                    OverrideEdit::Percent(input) => draft.percent_str = input,
+                    OverrideEdit::SubmitPercent(percent) => {
+                        draft.percent = percent;
+                        draft.percent_str = percent.to_string();
+                    },
+                    OverrideEdit::Minutes(input) => draft.minutes_str = input,
+                    OverrideEdit::SubmitMinutes(minutes) => {
+                        draft.minutes = minutes;
+                        draft.minutes_str = minutes.to_string();
+                    },
+                }
+                Task::none()
+            },
+            Message::GrantOverride(percent, minutes) => {
+                let grant = Override::new(percent, minutes, self.clock.now());
+                if let Err(error) = grant.save() {
+                    eprintln!("Failed to save the temporary limit: {}", error);
+                }
+                self.limit_override = Some(grant);
+                self.audit(Event::Override { percent: grant.percent, minutes });
+                // The override is only enforced by the limiter, which is saved on so a restart brings both back
+                if !self.limiter {
+                    self.limiter = true;
+                    self.log_limiter(Some(Cause::User));
+                    self.save_limit();
+                }
+                self.sync_engine();
+                Task::none()
+            },
+            Message::EndOverride => {
+                self.end_override();
+                Task::none()
+            },
+            Message::OverrideTick => {
+                if self.limit_override.is_some_and(|grant| !grant.is_active(self.clock.now())) {
+                    self.end_override();
+                }
+                Task::none()
+            },
+            Message::EditTamper(edit) => {
+                self.edit_tamper(edit);
+                self.save_settings();
+                Task::none()
+            },
+            Message::SelectTab(tab) => {
+                self.save_typed();
+                if tab == Tab::Audit {
+                    self.audit_entries = audit::read_recent(AUDIT_ROWS);
+                }
+                self.tab = tab;
+                Task::none()
+            },
+            Message::FilterAudit(filter) => {
+                self.audit_filter = filter;
+                Task::none()
+            },
+            Message::SearchAudit(search) => {
+                self.audit_search = search;
+                Task::none()
+            },
+            Message::RefreshAudit => {
+                self.audit_entries = audit::read_recent(AUDIT_ROWS);
+                Task::none()
+            },
+            Message::SelectSpan(span) => {
+                self.stats_span = span;
+                Task::none()
+            },
+            Message::EditExport(edit) => {
+                let draft = &mut self.export_draft;
+                match edit {
+                    ExportEdit::Format(format) => draft.format = format,
+                    ExportEdit::From(from) => draft.from = from,
+                    ExportEdit::To(to) => draft.to = to,
+                    ExportEdit::Dir(dir) => draft.dir = dir,
+                }
+                Task::none()
+            },
+            Message::Export => {
+                let draft = &self.export_draft;
+                let (range, history, dir, format) = (draft.range(), self.history.clone(), draft.dir.clone(), draft.format);
+                // Reading the audit log and writing the files stay off the UI thread
+                let export = move || {
+                    let written = range.and_then(|range| {
+                        Export::collect(range, &history, &audit::read_recent(usize::MAX))
+                            .write(&PathBuf::from(&dir), format)
+                            .map_err(|error| format!("Failed to export: {}", error))
+                    });
+                    match written {
+                        Ok(files) => format!("Wrote {} file(s) to {}", files.len(), dir),
+                        Err(error) => error,
+                    }
+                };
+                Task::perform(tokio::task::spawn_blocking(export), |message| {
+                    Message::Exported(message.unwrap_or_else(|error| format!("Failed to export: {}", error)))
+                })
+            },
+            Message::Exported(message) => {
+                self.export_draft.message = Some(message);
+                Task::none()
+            },
+            Message::InputVolChange(volume) => {
+                self.input_limits.expect_write(volume);
+                if let VolumeCommand::SetInputVol(Some(_)) = self.send_command(VolumeCommand::SetInputVol(Some(Volume::from(volume)))) {
+                    self.input_volume = Some(volume);
+                }
+                Task::none()
+            },
+            Message::ToggleSafeVolume(enabled) => {
+                if enabled {
+                    let volume = self.safe_str.parse().unwrap_or(DEFAULT_SAFE_VOLUME).min(100);
+                    self.settings.safe_volume.insert(self.output_device.clone(), volume);
+                } else {
+                    self.settings.safe_volume.remove(&self.output_device);
+                }
+                self.save_settings();
+                Task::none()
+            },
+            Message::ChangeSafeVolume(input) => {
+                self.safe_str = input;
+                Task::none()
+            },
+            Message::SubmitSafeVolume(volume) => {
+                self.settings.safe_volume.insert(self.output_device.clone(), volume);
+                self.safe_str = volume.to_string();
+                self.save_settings();
+                Task::none()
+            },
+            Message::ToggleStartupVolume(enabled) => {
+                self.settings.startup_volume.enabled = enabled;
+                self.save_settings();
+                Task::none()
+            },
+            Message::ChangeStartupVolume(input) => {
+                self.startup_str = input;
+                Task::none()
+            },
+            Message::SubmitStartupVolume(volume) => {
+                self.settings.startup_volume.percent = volume;
+                self.startup_str = volume.to_string();
+                self.save_settings();
+                Task::none()
+            },
+            Message::AutoLimiter => {
+                match self.auto_limiter() {
+                    Some(message) => Task::perform(async {}, move |_| message.clone()),
+                    None => Task::none(),
                 }
             }
             Message::AutoCheck(status) => {
                 self.autocheck = status;
-                println!("STATUS: {}", status);
-                println!("SCANNER: {:?}", self.scanner);
                 if status {
                     if self.scanner.is_none() {
                         let clone = Arc::clone(&self.mutex);
@@ -340,7 +1101,7 @@ impl VolControl {
                         self.tx_scanner.replace(tx);
                         self.scanner.replace( thread::spawn(move || {
                                 *count.lock().unwrap() += 1;
-                                while !rx.try_recv().is_ok() {
+                                while rx.try_recv().is_err() {
                                     thread::sleep(Duration::from_secs(1));
                                     let mut muter = clone.lock().unwrap();
                                     if get_sound_devices().len() != muter.len() {
@@ -349,96 +1110,63 @@ impl VolControl {
                                 }
                             })
                         );
-                        println!("threadcount {}", self.thread_count.lock().unwrap());
-                        println!("{:?}", self.devices);
                     }
                 } else {
                     if self.scanner.is_some() {
-                        println!("Shutting Thread Down?");
                         let tx = self.tx_scanner.take().unwrap();
                         tx.send(()).unwrap();
                         match self.scanner.take().unwrap().join() {
-                            Ok(()) => {println!("Reset Thread?"); self.scanner = None; *self.thread_count.lock().unwrap() -= 1;},
-                            Err(_) => {println!("UpdateError"); self.error = Some(Error::UpdateError); self.error_length = 0;}
+                            Ok(()) => {self.scanner = None; *self.thread_count.lock().unwrap() -= 1;},
+                            Err(_) => {self.error = Some(Error::UpdateError); self.error_length = 0;}
                         };
                     }
                 }
                 Task::none()
             }
-            Message::ChangeByOne(increase, limit) => {
-                if limit == true {
-                    if self.percent.to_string() == self.percent_str && !self.limiter{
-                        if increase && self.percent < 100{
-                            self.percent += 1;
-                        } else if self.percent > 0 {
-                            self.percent -= 1;
-                        }
-                        match self.percent {
-                            20 => {
-                                self.sel_lim.replace(BuiltIn::Twenty);
-                            },
-                            50 => {
-                                self.sel_lim.replace(BuiltIn::Fifty);
-                            },
-                            80 => {
-                                self.sel_lim.replace(BuiltIn::Eighty);
-                            },
-                            _ => {
-                                self.sel_lim.replace(BuiltIn::Custom);
-                            },
-                        }
-                        self.percent_str = self.percent.to_string();
-                        Task::none()
-                    } else if self.limiter {
-                         Task::batch(vec![
-                            Task::perform(async {}, move |_| Message::DisableLimit),
-                            Task::perform(async {}, move |_| Message::ConfirmPercent(limit,false)),
-                            Task::perform(async {}, move |_| Message::ChangeByOne(increase, limit)),
-                            Task::perform(async {}, move |_| Message::EnableLimit),
-                        ])
+            Message::SystemVolChange => {
+                if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) && vol.percent_u8() != self.volume() {
+                    let volume = vol.percent_u8();
+                    let clamped = self.limits.engine_wrote(volume);
+                    let controls = self.controls();
+                    // Changes made outside the app count as the user's choice, the ones we or the engine made don't
+                    if controls.set == Some(volume) || clamped {
+                        controls.show(volume);
                     } else {
-                        Task::batch(vec![
-                            Task::perform(async {}, move |_| Message::ConfirmPercent(limit, false)),
-                            Task::perform(async {}, move |_| Message::ChangeByOne(increase, limit)),
-                        ])
-                    }
-                } else {
-                    if self.volume.to_string() == self.vol_str {
-                        if increase && self.volume < 100{
-                            if (self.volume < self.percent && self.limiter) || !self.limiter {
-                                self.volume += 1;
-                            }
-                        } else if self.volume > 0 {
-                            self.volume -= 1;
-                        }
-                        self.send_command(VolumeCommand::SetVol(Some(self.volume as f32 / 100.0)));
-                        self.vol_str = self.volume.to_string();
-                        Task::none()
-                    } else {
-                        Task::batch(vec![
-                            Task::perform(async {}, move |_| Message::ConfirmPercent(limit, false)),
-                            Task::perform(async {}, move |_| Message::ChangeByOne(increase, limit)),
-                        ])
+                        controls.choose(volume);
+                        self.save_volumes();
                     }
                 }
-            },
-            Message::SystemVolChange => {
-                if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) && (vol * 100.0) as u8 != self.volume {
-                    self.volume = (vol * 100.0) as u8;
-                    self.vol_str = self.volume.to_string();
+                if let Some(peak) = self.limits.take_over_amplified() {
+                    self.over_amplified = Some((peak, self.clock.now()));
+                }
+                let clamp = self.limits.take_clamp();
+                if let Some((before, after)) = clamp {
+                    self.audit(Event::Clamp { before, after, device: self.output_device.clone() });
+                }
+                self.record_history(clamp.is_some());
+                if let Some((before, after)) = self.input_limits.take_clamp() {
+                    self.audit(Event::Clamp { before, after, device: self.input_device.clone().unwrap_or_default() });
+                }
+                let now = self.clock.now();
+                if self.muted_until.is_some_and(|until| now >= until) {
+                    self.muted_until = None;
                 }
+                // Everything the engine caught since the last poll counts as one attempt
+                if self.limits.take_attempts() > 0 && self.tamper.record(now, &self.settings.tamper) {
+                    self.escalate();
+                }
+                self.record_dose();
                 Task::none()
             },
             Message::SliderVolChange(volume, limit) => {
                 if limit {
                     if volume != self.percent {
                         self.percent = volume;
+                        self.save_limit();
                     }
                 } else {
-                    if volume != self.volume {
-                        self.send_command(VolumeCommand::SetVol(Some(volume as f32 / 100.0)));
-                        self.volume = volume;
-                        self.vol_str = self.volume.to_string();
+                    if volume != self.volume() {
+                        self.choose_volume(volume);
                     }
                 }
                 Task::none()
@@ -467,14 +1195,15 @@ impl VolControl {
                     }
                     if change_str {
                         self.percent_str = self.percent.to_string();
+                        self.save_limit();
                     }
                     self.sel_lim.replace(new);
                     task
                 } else {
                     Task::batch(vec![
-                        Task::perform(async {}, move |_| Message::DisableLimit),
+                        Task::perform(async {}, move |_| Message::DisableLimit(None)),
                         Task::perform(async {}, move |_| Message::ChangeLimitSel(new)),
-                        Task::perform(async {}, move |_| Message::EnableLimit),
+                        Task::perform(async {}, move |_| Message::EnableLimit(None)),
                     ])
                 }
                 
@@ -504,9 +1233,9 @@ impl VolControl {
             Message::OnToggle(toggle) => {
                 self.auto_autolimiter = false;
                 if toggle {
-                    Task::perform(async {}, |_| Message::EnableLimit)
+                    Task::perform(async {}, |_| Message::EnableLimit(Some(Cause::User)))
                 } else {
-                    Task::perform(async {}, |_| Message::DisableLimit)
+                    Task::perform(async {}, |_| Message::DisableLimit(Some(Cause::User)))
                 }
             },
             Message::OnPick(device) => {
@@ -517,25 +1246,294 @@ impl VolControl {
                     ]
                 )
             },
+            Message::ChangeTheme(choice) => {
+                self.theme_choice = choice;
+                self.settings.theme = self.theme_choice.name();
+                self.save_settings();
+                Task::none()
+            },
+            Message::ReloadThemes => {
+                // Directory scans and file reads stay off the UI thread, only a change comes back
+                let known = self.theme_stamps.clone();
+                let check = move || {
+                    let stamps = theme_file::scan();
+                    (stamps != known).then(|| (theme_file::load_themes(), stamps))
+                };
+                Task::perform(tokio::task::spawn_blocking(check), |changed| match changed {
+                    Ok(Some((themes, stamps))) => Message::ThemesChanged(themes, stamps),
+                    _ => Message::None,
+                })
+            },
+            Message::ThemesChanged(themes, stamps) => {
+                self.custom_themes = themes;
+                self.theme_stamps = stamps;
+                Task::none()
+            },
+            Message::DetectSystemTheme => {
+                Task::perform(tokio::task::spawn_blocking(styles::system_theme), |theme| {
+                    theme.map_or(Message::None, Message::SystemThemeChanged)
+                })
+            },
+            Message::SystemThemeChanged(theme) => {
+                self.system_theme = theme;
+                Task::none()
+            },
+            Message::ChangeDoseStandard(standard) => {
+                self.settings.dosimeter.standard = standard;
+                self.dosimeter.standard = standard;
+                self.save_settings();
+                self.sync_engine();
+                Task::none()
+            },
+            Message::ToggleDoseTighten(tighten) => {
+                self.settings.dosimeter.tighten = tighten;
+                self.save_settings();
+                self.sync_engine();
+                Task::none()
+            },
+            Message::ChangeSensitivity(input) => {
+                self.sensitivity_str = input;
+                Task::none()
+            },
+            Message::SubmitSensitivity(max_spl_db) => {
+                self.settings.sensitivity.insert(self.output_device.clone(), max_spl_db as f32);
+                self.sensitivity_str = max_spl_db.to_string();
+                self.save_settings();
+                self.sync_engine();
+                Task::none()
+            },
+            Message::ToggleSchedule(enabled) => {
+                self.settings.schedule.enabled = enabled;
+                self.active_rule = None;
+                self.save_settings();
+                if enabled {
+                    Task::perform(async {}, |_| Message::ScheduleTick)
+                } else {
+                    Task::none()
+                }
+            },
+            Message::ScheduleTick => {
+                let active = self.settings.schedule.active_at(self.clock.as_ref(), &self.output_device).map(|(_, rule)| rule.clone());
+                if active == self.active_rule {
+                    return Task::none();
+                }
+                // Rules only act when they start, so manual changes hold until the next rule takes over
+                self.active_rule = active;
+                match self.active_rule.as_ref().map(|rule| rule.action) {
+                    Some(RuleAction::Limit(percent)) => Task::batch(vec![
+                        Task::perform(async {}, move |_| Message::SetLimit(percent)),
+                        Task::perform(async {}, |_| Message::EnableLimit(Some(Cause::Schedule))),
+                    ]),
+                    Some(RuleAction::Unlimited) => Task::perform(async {}, |_| Message::DisableLimit(Some(Cause::Schedule))),
+                    None => Task::none(),
+                }
+            },
+            Message::AddRule => {
+                let start = schedule::parse_time("21:00").unwrap_or_default();
+                let end = schedule::parse_time("07:00").unwrap_or_default();
+                let rule = Rule::new(format!("Rule {}", self.settings.schedule.rules.len() + 1), &schedule::WEEK, start, end, RuleAction::Limit(self.percent));
+                self.rule_drafts.push(RuleDraft::new(&rule));
+                self.settings.schedule.rules.push(rule);
+                self.save_settings();
+                Task::none()
+            },
+            Message::AddQuietHours => {
+                for rule in Schedule::quiet_hours() {
+                    self.rule_drafts.push(RuleDraft::new(&rule));
+                    self.settings.schedule.rules.push(rule);
+                }
+                self.save_settings();
+                Task::none()
+            },
+            Message::EditRule(index, edit) => {
+                // A name is saved once it's submitted or left, not at every keystroke
+                let typing = matches!(edit, RuleEdit::Name(_));
+                self.edit_rule(index, edit);
+                if !typing {
+                    self.save_settings();
+                }
+                Task::none()
+            },
+            Message::EditRamp(edit) => {
+                self.edit_ramp(edit);
+                self.save_settings();
+                self.sync_engine();
+                Task::none()
+            },
+            Message::StartSleep(sleep) => {
+                self.sleep_timer = Some(sleep.timer(Instant::now()));
+                Task::none()
+            },
+            Message::CancelSleep => {
+                self.sleep_timer = None;
+                Task::none()
+            },
+            Message::SleepTick => {
+                let now = Instant::now();
+                let Some(mut timer) = self.sleep_timer else {
+                    return Task::none();
+                };
+                // The fade only ever lowers the volume, so it can't lift it past the limiter's ceiling
+                if let Some(target) = timer.target(now, self.volume()) && target < self.volume() {
+                    self.set_volume(target);
+                }
+                if !timer.is_done(now) {
+                    self.sleep_timer = Some(timer);
+                } else if self.sleep_timer.take().is_some() {
+                    match timer.after {
+                        AfterSleep::Nothing => {},
+                        AfterSleep::Mute => {
+                            self.send_command(VolumeCommand::SetMute(Some(true)));
+                        },
+                        AfterSleep::Pause => {
+                            if let Err(error) = media::pause_media() {
+                                eprintln!("Failed to pause media: {}", error);
+                            }
+                        },
+                    }
+                }
+                Task::none()
+            },
+            Message::EditSleep(edit) => {
+                let (sleep, draft) = (&mut self.settings.sleep, &mut self.sleep_draft);
+                match edit {
+                    SleepEdit::Minutes(input) => draft.minutes = input,
+                    SleepEdit::SubmitMinutes(minutes) => {
+                        sleep.minutes = minutes;
+                        draft.minutes = minutes.to_string();
+                    },
+                    SleepEdit::Fade(input) => draft.fade = input,
+                    SleepEdit::SubmitFade(minutes) => {
+                        sleep.fade_minutes = minutes;
+                        draft.fade = minutes.to_string();
+                    },
+                    SleepEdit::Floor(input) => draft.floor = input,
+                    SleepEdit::SubmitFloor(floor) => {
+                        sleep.floor = floor;
+                        draft.floor = floor.to_string();
+                    },
+                    SleepEdit::After(after) => sleep.after = after,
+                }
+                self.save_settings();
+                Task::none()
+            },
+            Message::PollRequests => {
+                let mut tasks = Vec::new();
+                for envelope in ipc::take() {
+                    let message = match envelope.request {
+                        Request::Sleep(sleep) => Message::StartSleep(sleep),
+                        Request::CancelSleep => Message::CancelSleep,
+                        Request::Lock => Message::Lock,
+                        Request::Override(percent, minutes) => Message::GrantOverride(percent, minutes),
+                        Request::CancelOverride => Message::EndOverride,
+                    };
+                    if self.locked && envelope.request.is_protected() {
+                        // Handled right away, the proof vouches for this request only
+                        if self.proof_matches(&envelope) {
+                            tasks.push(self.handle(message));
+                        } else {
+                            eprintln!("Refusing \"{}\" while locked: wrong or missing PIN", envelope.request);
+                        }
+                    } else {
+                        tasks.push(Task::perform(async {}, move |_| message.clone()));
+                    }
+                }
+                Task::batch(tasks)
+            },
+            Message::ToggleSlew(enabled) => {
+                self.settings.slew.enabled = enabled;
+                self.update_limits();
+                Task::none()
+            },
+            Message::ChangeSlew(input) => {
+                self.slew_str = input;
+                Task::none()
+            },
+            Message::SubmitSlew(rate) => {
+                self.settings.slew.rate = rate;
+                self.slew_str = rate.to_string();
+                self.update_limits();
+                Task::none()
+            },
+            Message::ToggleFloor(enabled) => {
+                self.settings.floor.enabled = enabled;
+                self.update_limits();
+                Task::none()
+            },
+            Message::ChangeFloor(input) => {
+                self.floor_str = input;
+                Task::none()
+            },
+            Message::SubmitFloor(percent) => {
+                self.settings.floor.percent = percent;
+                self.floor_str = percent.to_string();
+                self.update_limits();
+                Task::none()
+            },
+            Message::ToggleAllowMute(allow) => {
+                self.settings.allow_mute = allow;
+                self.update_limits();
+                Task::none()
+            },
+            Message::ToggleLock(enabled) => {
+                self.settings.lock.enabled = enabled;
+                self.update_limits();
+                Task::none()
+            },
+            Message::ChangeLock(input) => {
+                self.lock_str = input;
+                Task::none()
+            },
+            Message::SubmitLock(percent) => {
+                self.settings.lock.percent = percent;
+                self.lock_str = percent.to_string();
+                self.update_limits();
+                Task::none()
+            },
+            Message::FocusNext(backwards) => {
+                self.save_typed();
+                if backwards {
+                    focus_previous()
+                } else {
+                    focus_next()
+                }
+            },
         }
     }
     // NextUI
     pub fn view(&self) -> Element<'_, Message> {
-        Column::new().push(text("Volume Limiter").center().size(20).width(Length::Fill)).push(
+        let metrics = self.theme_choice.metrics(&self.custom_themes);
+        let tab = |label, tab| button(text(label)).on_press_maybe((self.tab != tab).then_some(Message::SelectTab(tab))).style(buttons::themed(metrics));
+        Column::new()
+            .push(Row::new().push(tab("Controls", Tab::Controls)).push(tab("Statistics", Tab::Stats)).push(tab("Audit Log", Tab::Audit)).spacing(10).padding([10, 20]))
+            .push_maybe(self.settings_error.as_ref().map(|error| {
+                Row::new().push(text(format!("Settings not loaded, changes won't be saved until config.toml is fixed: {}", error)).style(text::danger)).padding([0, 20])
+            }))
+            .push(match self.tab {
+                Tab::Controls => self.controls_view(metrics),
+                Tab::Stats => self.stats_view(metrics),
+                Tab::Audit => self.audit_view(metrics),
+            })
+            .into()
+    }
+
+    fn controls_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let curve = self.settings.curve;
+        scrollable(Column::new().push(text("Volume Limiter").center().size(20).width(Length::Fill)).push(
             HovContainer::new()
             .push(Column::new()
                 .push(text("Volume Controls").width(Length::Fill).size(18).height(30).center())
                 .push(
                     Row::new()
                     .push(
-                        radio("Slider", InputType::Slider, self.input_vol, |selection| Message::ChangeVolInput(selection)))    
+                        radio("Slider", InputType::Slider, self.input_vol, Message::ChangeVolInput))    
                     .push(
-                        radio("Text", InputType::Text, self.input_vol, |selection| Message::ChangeVolInput(selection)))
+                        radio("Text", InputType::Text, self.input_vol, Message::ChangeVolInput))
                     .spacing(40)
                 ).width(Length::Fill).align_x(Alignment::Center)
             )
-                .on_hover(Message::None).on_exit(Message::None)
-                .style(hov_container_row::auto_style(get_rgb_color(150, 150, 150), get_rgb_color(100, 100, 255), 3, 15))
+                .on_hover(Message::None).on_exit(Message::None).focusable(true)
+                .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
                 .padding(20).width(Length::Fill))
 
             .push(HovContainer::new()
@@ -544,167 +1542,547 @@ impl VolControl {
                         if self.input_vol == Some(InputType::Slider) {
                             Column::new()
                                 .push(Row::new()
-                                    .push(slider(if self.limiter {0..=self.percent} else {0..=100}, self.volume,|vol| Message::SliderVolChange(vol, false)))
-                                    .push(text(self.volume)).padding(20).spacing(20).height(70).align_y(Alignment::Center)
+                                    .push(self.volume_slider(metrics, self.volume(), if self.limiter {self.ceiling()} else {100}, |vol| Message::SliderVolChange(vol, false)))
+                                    .push(text(self.level_label(self.volume()))).padding(20).spacing(20).height(70).align_y(Alignment::Center)
                                 )
                         } else {
                             Column::new()
-                                .push(Row::new().push(button(" + ").on_press(Message::ChangeByOne(true, false)))
-                                .push(text_input(&self.vol_str, &self.vol_str).on_input(|input| Message::ChangePercent(input, false)).on_submit(Message::ConfirmPercent(false, true)).style(
-                                    move |_: &Theme, status| {
-                                        match status {
-                                            _ => {
-                                                if self.error == Some(Error::ParseVolError) {
-                                                        text_input::Style{
-                                                            border: iced::Border{color: get_rgb_color(255, 0, 0), width: 1.0, ..Default::default()},
-                                                            value: get_rgb_color(255, 0, 0),
-                                                            background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                            icon: iced::Color::default(),
-                                                            placeholder: get_rgb_color(50, 50, 50),
-                                                            selection: get_rgb_color(20, 20, 100),
-                                                        }
-                                                    } else {
-                                                        text_input::Style{
-                                                            border: iced::Border{color:get_rgb_color(150, 150, 150), width: 1.0, ..Default::default()},
-                                                            value: get_rgb_color(255, 255, 255),
-                                                            background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                            icon: iced::Color::default(),
-                                                            placeholder: get_rgb_color(200, 200, 200),
-                                                            selection: get_rgb_color(20, 20, 100),
-                                                        }
-                                                    }
-                                                },
-                                            }
-                                        }
-                                    ).align_x(Alignment::Center).width(Length::Fixed(100.0)))
                                 .push(
-                                    button(" - ").on_press(Message::ChangeByOne(false, false))
-                                ).align_y(Alignment::Center).spacing(10).padding(20).height(70))
-                                .push_maybe(if self.error == Some(Error::ParseVolError) {Some(text("Please enter a number between 0 and 100!").color(get_rgb_color(255, 0, 0)))} else {None})
+                                    NumericInput::new(self.volume(), self.vol_str())
+                                        .style(inputs::themed(metrics))
+                                        .error_style(inputs::error(metrics))
+                                        .button_style(buttons::themed(metrics))
+                                        .range(if self.limiter {0..=self.ceiling()} else {0..=100})
+                                        .stepper(move |volume, up| curve.step(volume, up))
+                                        .on_input(|input| Message::ChangePercent(input, false))
+                                        .on_submit(|volume| Message::SubmitPercent(volume, false))
+                                )
+                                .push_maybe(self.settings.show_db.then(|| text(self.level_label(self.volume()))))
+                                .padding(20).spacing(10).align_x(Alignment::Center)
                         }                        
-                    ).width(Length::Fill).align_x(Alignment::Center)
-                ).on_hover(Message::None).on_exit(Message::None).style(
-                    hov_container_row::auto_style(get_rgb_color(150, 150, 150), get_rgb_color(100, 100, 255), 3, 15)
+                    )
+                    .push_maybe(self.balance.map(|balance| Row::new()
+                        .push(text("L"))
+                        .push(slider(balance::RANGE, balance, Message::BalanceChange).style(sliders::themed(metrics)))
+                        .push(text("R"))
+                        .padding([0, 20]).spacing(20).align_y(Alignment::Center)
+                    ))
+                    .push(Row::new()
+                        .push(toggler(self.settings.show_db).label("Show levels in dB").on_toggle(Message::ToggleDb))
+                        .push(text("Curve"))
+                        .push(pick_list(Curve::ALL, Some(self.settings.curve), Message::ChangeCurve))
+                        .padding(20).spacing(10).align_y(Alignment::Center)
+                    )
+                    .width(Length::Fill).align_x(Alignment::Center)
+                ).on_hover(Message::None).on_exit(Message::None).focusable(true).style(
+                    hov_container_row::palette_style(metrics.container_border, metrics.container_radius)
                 )
             )
             .push(HovContainer::new().push(Column::new().push(text("Limiter Controls").size(18).height(30).center())
             .push(Column::new()
                 .push(Row::new()
-                    .push(radio("20%", BuiltIn::Twenty, self.sel_lim, |selection| Message::ChangeLimitSel(selection)))
-                    .push(radio("50%", BuiltIn::Fifty, self.sel_lim, |selection| Message::ChangeLimitSel(selection)))
-                    .push(radio("80%", BuiltIn::Eighty, self.sel_lim, |selection| Message::ChangeLimitSel(selection)))
-                    .push(radio("Custom", BuiltIn::Custom, self.sel_lim, |selection| Message::ChangeLimitSel(selection)))
+                    .push(radio("20%", BuiltIn::Twenty, self.sel_lim, |choice| self.unlocked_or_none(Message::ChangeLimitSel(choice))))
+                    .push(radio("50%", BuiltIn::Fifty, self.sel_lim, |choice| self.unlocked_or_none(Message::ChangeLimitSel(choice))))
+                    .push(radio("80%", BuiltIn::Eighty, self.sel_lim, |choice| self.unlocked_or_none(Message::ChangeLimitSel(choice))))
+                    .push(radio("Custom", BuiltIn::Custom, self.sel_lim, |choice| self.unlocked_or_none(Message::ChangeLimitSel(choice))))
                     .push_maybe(
                         if self.sel_lim == Some(BuiltIn::Custom) {
                             Some(
-                                text_input(&self.percent.to_string(), &self.percent_str)
-                                    // .on_input_maybe(if !self.limiter {Some(|input| Message::ChangePercent(input, true))} else {None} )
+                                NumericInput::new(self.percent, &self.percent_str)
+                                    .style(inputs::themed(metrics))
+                                    .error_style(inputs::error(metrics))
+                                    .range(self.limit_range())
                                     .on_input(|input| Message::ChangePercent(input, true))
-                                    .on_submit(Message::ConfirmPercent(true, false))
-                                    .size(14)
-                                    .style(
-                                        move |_: &Theme, status| {
-                                            match status {
-                                                _ => {
-                                                    if self.error == Some(Error::ParseError) {
-                                                        text_input::Style{
-                                                            border: iced::Border{color: get_rgb_color(255, 0, 0), width: 1.0, ..Default::default()},
-                                                            value: get_rgb_color(255, 0, 0),
-                                                            background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                            icon: iced::Color::default(),
-                                                            placeholder: get_rgb_color(50, 50, 50),
-                                                            selection: get_rgb_color(20, 20, 100),
-                                                        }
-                                                    } else {
-                                                        text_input::Style{
-                                                            border: iced::Border{color:get_rgb_color(150, 150, 150), width: 1.0, ..Default::default()},
-                                                            value: get_rgb_color(255, 255, 255),
-                                                            background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                            icon: iced::Color::default(),
-                                                            placeholder: get_rgb_color(200, 200, 200),
-                                                            selection: get_rgb_color(20, 20, 100),
-                                                        }
-                                                    }   
-                                                },
-                                            }
-                                        }
-                                    ).width(Length::Fixed(40.0)).align_x(Alignment::Center)
+                                    .on_submit(|percent| Message::SubmitPercent(percent, true))
+                                    .disabled(self.locked)
+                                    .buttons(false)
+                                    .size(14.0)
+                                    .width(Length::Fixed(40.0))
                             )
                         } else {
                             None
                         }
                     ).align_y(Alignment::Center).height(30).spacing(20)
                 ).spacing(40)
-                .push_maybe(if self.error.is_some() && self.error == Some(Error::AdjustWhileOn) {Some(text("Please Turn off the Volume Limiter to Adjust!").color(get_rgb_color(255, 0, 0)))} else {None})
+                .push_maybe(if self.error.is_some() && self.error == Some(Error::AdjustWhileOn) {Some(text("Please Turn off the Volume Limiter to Adjust!").style(text::danger))} else {None})
             )
             .push(Row::new()
                 .push(
                     Column::new()
-                        .push(toggler(self.limiter).label("Enable Volume Limiter").on_toggle(|toggle| Message::OnToggle(toggle)))
-                        .push(toggler(self.autolimiter).label("Enable Auto Limiter").on_toggle(|toggle| Message::ChangeAutoLimiter(toggle)))
-                        .push(toggler(self.autocheck).label("Enable Auto Check Device Update").on_toggle(|toggle| Message::AutoCheck(toggle)))
-                        .push(pick_list(self.all_devices.clone(), self.device.clone(), Message::OnPick))
+                        .push(toggler(self.limiter).label("Enable Volume Limiter").on_toggle_maybe((!self.locked || !self.limiter).then_some(Message::OnToggle)))
+                        .push(toggler(self.autolimiter).label("Enable Auto Limiter").on_toggle_maybe(self.unlocked(Message::ChangeAutoLimiter)))
+                        .push(toggler(self.autocheck).label("Enable Auto Check Device Update").on_toggle(Message::AutoCheck))
+                        .push(pick_list(self.locked_options(self.all_devices.clone(), self.device.as_ref()), self.device.clone(), |device| self.unlocked_or_none(Message::OnPick(device))))
+                        .push(Row::new()
+                            .push(toggler(self.settings.slew.enabled).label("Limit rise to").on_toggle_maybe(self.unlocked(Message::ToggleSlew)))
+                            .push(
+                                NumericInput::new(self.settings.slew.rate, &self.slew_str)
+                                    .style(inputs::themed(metrics))
+                                    .error_style(inputs::error(metrics))
+                                    .disabled(self.locked)
+                                    .range(1..=100)
+                                    .on_input(Message::ChangeSlew)
+                                    .on_submit(Message::SubmitSlew)
+                                    .buttons(false)
+                                    .show_error(false)
+                                    .width(Length::Fixed(45.0))
+                            )
+                            .push(text("%/s"))
+                            .align_y(Alignment::Center).spacing(8))
+                        .push(Row::new()
+                            .push(toggler(self.settings.floor.enabled).label("Never below").on_toggle_maybe(self.unlocked(Message::ToggleFloor)))
+                            .push(
+                                NumericInput::new(self.settings.floor.percent, &self.floor_str)
+                                    .style(inputs::themed(metrics))
+                                    .error_style(inputs::error(metrics))
+                                    .disabled(self.locked)
+                                    .on_input(Message::ChangeFloor)
+                                    .on_submit(Message::SubmitFloor)
+                                    .buttons(false)
+                                    .show_error(false)
+                                    .width(Length::Fixed(45.0))
+                            )
+                            .push(text("%"))
+                            .align_y(Alignment::Center).spacing(8))
+                        .push(Row::new()
+                            .push(toggler(self.settings.lock.enabled).label("Lock volume at").on_toggle_maybe(self.unlocked(Message::ToggleLock)))
+                            .push(
+                                NumericInput::new(self.settings.lock.percent, &self.lock_str)
+                                    .style(inputs::themed(metrics))
+                                    .error_style(inputs::error(metrics))
+                                    .disabled(self.locked)
+                                    .on_input(Message::ChangeLock)
+                                    .on_submit(Message::SubmitLock)
+                                    .buttons(false)
+                                    .show_error(false)
+                                    .width(Length::Fixed(45.0))
+                            )
+                            .push(text("%"))
+                            .align_y(Alignment::Center).spacing(8))
+                        .push(toggler(self.settings.allow_mute).label("Let mute through the floor and lock").on_toggle_maybe(self.unlocked(Message::ToggleAllowMute)))
                     .align_x(Alignment::Center).padding(10).width(Length::FillPortion(1)))
                     .push(Column::new()
-                        .push(Row::new()
-                            .push(button(" + ").on_press(Message::ChangeByOne(true, true)))
-                            .push(text_input(&self.percent.to_string(), &self.percent_str)
-                                // .on_input_maybe(if !self.limiter {Some(|input| Message::ChangePercent(input, true))} else {None} )
+                        .push(
+                            NumericInput::new(self.percent, &self.percent_str)
+                                .style(inputs::themed(metrics))
+                                .error_style(inputs::error(metrics))
+                                .range(self.limit_range())
+                                .button_style(buttons::themed(metrics))
+                                .stepper(move |percent, up| curve.step(percent, up))
                                 .on_input(|input| Message::ChangePercent(input, true))
-                                .on_submit(Message::ConfirmPercent(true, false))
-                                .style(
-                                    move |_: &Theme, status| {
-                                        match status {
-                                            _ => {
-                                                if self.error == Some(Error::ParseError) {
-                                                    text_input::Style{
-                                                        border: iced::Border{color: get_rgb_color(255, 0, 0), width: 1.0, ..Default::default()},
-                                                        value: get_rgb_color(255, 0, 0),
-                                                        background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                        icon: iced::Color::default(),
-                                                        placeholder: get_rgb_color(50, 50, 50),
-                                                        selection: get_rgb_color(20, 20, 100),
-                                                    }
-                                                } else {
-                                                    text_input::Style{
-                                                        border: iced::Border{color:get_rgb_color(150, 150, 150), width: 1.0, ..Default::default()},
-                                                        value: get_rgb_color(255, 255, 255),
-                                                        background: iced::Background::Color(get_rgb_color(100, 100, 100)),
-                                                        icon: iced::Color::default(),
-                                                        placeholder: get_rgb_color(200, 200, 200),
-                                                        selection: get_rgb_color(20, 20, 100),
-                                                    }
-                                                }
-                                            },
-                                        }
-                                    }
-                                ).align_x(Alignment::Center).width(Length::Fixed(100.0))
-                            )
-                            .push(button(" - ").on_press(Message::ChangeByOne(false, true)))
+                                .on_submit(|percent| Message::SubmitPercent(percent, true))
+                                .disabled(self.locked)
                         )
-                        .push_maybe(if self.error == Some(Error::ParseError) {Some(text("Please enter a number between 0 and 100!").color(get_rgb_color(255, 0, 0)))} else {None})
                         .push(text(format!("Current Volume Limit: {}", self.percent)))
-                        .push(text(format!{"Current Volume: {}", self.volume}))
+                        .push_maybe(if self.limiter && self.ceiling() < self.percent {Some(text(format!("Tightened by dose budget: {}", self.ceiling())))} else {None})
+                        .push(text(format!{"Current Volume: {}", self.volume()}))
                         .push(text("Hello World")).align_x(Alignment::Center).spacing(10).padding(20).width(Length::FillPortion(1))
                     ).padding(20)
                     .align_y(Alignment::Center)
                     .spacing(10)
                 ).align_x(Alignment::Center))
                 .on_hover(Message::None)
-                .on_exit(Message::None)
+                .on_exit(Message::None).focusable(true)
                 .style(
-                    hov_container_row::auto_style(get_rgb_color(150, 150, 150), get_rgb_color(100, 100, 255), 3, 15)
+                    hov_container_row::palette_style(metrics.container_border, metrics.container_radius)
                 ))
-            .push(vertical_space())
+            .push(self.lock_view(metrics))
+            .push(self.override_view(metrics))
+            .push(self.tamper_view(metrics))
+            .push(self.device_view(metrics))
+            .push(self.input_view(metrics))
+            .push(self.dose_view(metrics))
+            .push(self.schedule_view(metrics))
+            .push(self.sleep_view(metrics))
+            .push(Row::new()
+                .push(text("Theme"))
+                .push(pick_list(ThemeChoice::all(&self.custom_themes), Some(self.theme_choice.clone()), Message::ChangeTheme))
+                .align_y(Alignment::Center).spacing(10).padding(10))
             .push(Row::new().push(text("(C) Xephyris 2025").align_x(Alignment::Center).width(Length::Fill).center()).padding(10))
         .spacing(20)
-        .padding(10)
+        .padding(10))
         .into()
-        
     }
 
-    pub fn theme(_: &VolControl) -> Theme{
-        Theme::Dark
+    fn stats_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let span = self.stats_span;
+        let now = self.clock.now();
+        let summary = self.history.summary(span, now);
+        let share = if summary.observed.is_zero() {0.0} else {summary.at_ceiling.as_secs_f32() / summary.observed.as_secs_f32() * 100.0};
+        let devices = summary.devices.iter().fold(Column::new().spacing(4), |column, (device, time)| column.push(
+            text(format!("{}: {}", if device.is_empty() {"Unknown device"} else {device}, hours_minutes(*time)))
+        ));
+        scrollable(Column::new()
+            .push(HovContainer::new()
+                .push(Column::new()
+                    .push(text("Volume History").size(18).height(30).center())
+                    .push(Span::ALL.iter().fold(Row::new().spacing(20), |row, option| row.push(radio(option.to_string(), *option, Some(span), Message::SelectSpan))))
+                    .push(canvas(HistoryGraph::new(self.history.buckets(span, now, span.slices()))).width(Length::Fill).height(Length::Fixed(220.0)))
+                    .push(text("Volume (solid), ceiling (dashed), clamps (bars)").size(12))
+                    .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+                )
+                .on_hover(Message::None).on_exit(Message::None).focusable(true)
+                .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius)))
+            .push(HovContainer::new()
+                .push(Column::new()
+                    .push(text("Summary").size(18).height(30).center())
+                    .push(text(format!("Listening time: {}", hours_minutes(summary.observed))))
+                    .push(text(format!("At the ceiling: {} ({:.0}%)", hours_minutes(summary.at_ceiling), share)))
+                    .push(text(format!("Clamps: {}", summary.clamps)))
+                    .push(text("Time per device"))
+                    .push(devices)
+                    .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+                )
+                .on_hover(Message::None).on_exit(Message::None).focusable(true)
+                .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius)))
+            .push(self.export_view(metrics))
+            .spacing(20)
+            .padding(10))
+            .into()
+    }
+
+    fn export_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let draft = &self.export_draft;
+        let date = |placeholder, value, on_input: fn(String) -> ExportEdit| {
+            text_input(placeholder, value).on_input(move |input| Message::EditExport(on_input(input))).on_submit(Message::Export).style(inputs::themed(metrics)).width(Length::Fixed(110.0))
+        };
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Export").size(18).height(30).center())
+                .push(Format::ALL.iter().fold(Row::new().spacing(20), |row, format| row.push(
+                    radio(format.to_string(), *format, Some(draft.format), |format| Message::EditExport(ExportEdit::Format(format)))
+                )))
+                .push(Row::new()
+                    .push(text("From"))
+                    .push(date("YYYY-MM-DD", &draft.from, ExportEdit::From))
+                    .push(text("to"))
+                    .push(date("YYYY-MM-DD", &draft.to, ExportEdit::To))
+                    .align_y(Alignment::Center).spacing(10))
+                .push(Row::new()
+                    .push(text_input("Folder", &draft.dir).on_input(|dir| Message::EditExport(ExportEdit::Dir(dir))).on_submit(Message::Export).style(inputs::themed(metrics)).width(Length::Fixed(260.0)))
+                    .push(button(text("Export")).on_press(Message::Export).style(buttons::themed(metrics)))
+                    .align_y(Alignment::Center).spacing(10))
+                .push(text("Volume samples, clamps and time per device; history goes back a week").size(12))
+                .push_maybe(draft.message.as_ref().map(|message| text(message.clone())))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    // Newest first, narrowed by kind and a case-insensitive search of the description
+    fn audit_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let search = self.audit_search.to_lowercase();
+        let entries = self.audit_entries.iter().rev()
+            .filter(|entry| self.audit_filter.matches(entry.event.kind()))
+            .filter(|entry| search.is_empty() || entry.event.to_string().to_lowercase().contains(&search))
+            .fold(Column::new().spacing(4), |column, entry| column.push(
+                text(format!("{}  {}", entry.time.format("%Y-%m-%d %H:%M:%S"), entry.event)).size(13)
+            ));
+        scrollable(Column::new()
+            .push(HovContainer::new()
+                .push(Column::new()
+                    .push(text("Audit Log").size(18).height(30).center())
+                    .push(Row::new()
+                        .push(pick_list(AuditFilter::all(), Some(self.audit_filter), Message::FilterAudit))
+                        .push(text_input("Search", &self.audit_search).on_input(Message::SearchAudit).style(inputs::themed(metrics)).width(Length::Fixed(200.0)))
+                        .push(button(text("Refresh")).on_press(Message::RefreshAudit).style(buttons::themed(metrics)))
+                        .align_y(Alignment::Center).spacing(10))
+                    .push_maybe(self.audit_entries.is_empty().then(|| text("Nothing logged yet")))
+                    .push(entries.width(Length::Fill))
+                    .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+                )
+                .on_hover(Message::None).on_exit(Message::None).focusable(true)
+                .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius)))
+            .padding(10))
+            .into()
+    }
+
+    fn lock_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let content = match (&self.pin_hash, self.locked) {
+            (_, true) => Column::new()
+                .push(text("Settings are locked"))
+                .push(Row::new()
+                    .push(text_input("PIN", &self.pin_str).secure(true).on_input(Message::ChangePin).on_submit(Message::Unlock).style(inputs::themed(metrics)).width(Length::Fixed(160.0)))
+                    .push(button(text("Unlock")).on_press(Message::Unlock).style(buttons::themed(metrics)))
+                    .align_y(Alignment::Center).spacing(10)),
+            (Some(_), false) => Column::new()
+                .push(Row::new()
+                    .push(button(text("Lock now")).on_press(Message::Lock).style(buttons::themed(metrics)))
+                    .push(button(text("Remove PIN")).on_press(Message::RemovePin).style(buttons::themed(metrics)))
+                    .spacing(10))
+                .push(Row::new()
+                    .push(text_input("New PIN", &self.new_pin_str).secure(true).on_input(Message::ChangeNewPin).on_submit(Message::SetPin).style(inputs::themed(metrics)).width(Length::Fixed(160.0)))
+                    .push(button(text("Change PIN")).on_press(Message::SetPin).style(buttons::themed(metrics)))
+                    .align_y(Alignment::Center).spacing(10)),
+            (None, false) => Column::new()
+                .push(text("Set a PIN to stop others loosening the limits or quitting the app"))
+                .push(Row::new()
+                    .push(text_input("PIN", &self.new_pin_str).secure(true).on_input(Message::ChangeNewPin).on_submit(Message::SetPin).style(inputs::themed(metrics)).width(Length::Fixed(160.0)))
+                    .push(button(text("Set PIN")).on_press(Message::SetPin).style(buttons::themed(metrics)))
+                    .align_y(Alignment::Center).spacing(10)),
+        };
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Parental Lock").size(18).height(30).center())
+                .push(content.align_x(Alignment::Center).spacing(10))
+                .push_maybe(self.lock_message.as_ref().map(|message| text(message.clone())))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn override_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let draft = &self.override_draft;
+        let input = |value, input, range, on_input: fn(String) -> OverrideEdit, on_submit: fn(u8) -> OverrideEdit| {
+            NumericInput::new(value, input)
+                .style(inputs::themed(metrics))
+                .error_style(inputs::error(metrics))
+                .disabled(self.locked)
+                .range(range)
+                .on_input(move |input| Message::EditOverride(on_input(input)))
+                .on_submit(move |value| Message::EditOverride(on_submit(value)))
+                .buttons(false)
+                .show_error(false)
+                .width(Length::Fixed(45.0))
+        };
+        let now = self.clock.now();
+        let status = match self.limit_override.filter(|grant| grant.is_active(now)) {
+            Some(grant) => {
+                let remaining = grant.remaining(now).num_seconds();
+                format!("Limit at {}% for another {}:{:02}", grant.percent, remaining / 60, remaining % 60)
+            },
+            None => String::from("No temporary limit"),
+        };
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Temporary Limit").size(18).height(30).center())
+                .push(Row::new()
+                    .push(text("Set the limit to"))
+                    .push(input(draft.percent, &draft.percent_str, 1..=100, OverrideEdit::Percent, OverrideEdit::SubmitPercent))
+                    .push(text("% for"))
+                    .push(input(draft.minutes, &draft.minutes_str, 1..=240, OverrideEdit::Minutes, OverrideEdit::SubmitMinutes))
+                    .push(text("min"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(text(status))
+                .push(if self.limit_override.is_some() {
+                    button(text("End now")).on_press_maybe(self.unlocked(Message::EndOverride)).style(buttons::themed(metrics))
+                } else {
+                    button(text("Grant")).on_press_maybe(self.unlocked(Message::GrantOverride(draft.percent, draft.minutes))).style(buttons::themed(metrics))
+                })
+                .push_maybe(self.locked.then(|| text("Unlock with the PIN to grant or end a temporary limit")))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn tamper_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let tamper = &self.settings.tamper;
+        let draft = &self.tamper_draft;
+        let input = |value, input, range, on_input: fn(String) -> TamperEdit, on_submit: fn(u8) -> TamperEdit| {
+            NumericInput::new(value, input)
+                .style(inputs::themed(metrics))
+                .error_style(inputs::error(metrics))
+                .disabled(self.locked)
+                .range(range)
+                .on_input(move |input| Message::EditTamper(on_input(input)))
+                .on_submit(move |value| Message::EditTamper(on_submit(value)))
+                .buttons(false)
+                .show_error(false)
+                .width(Length::Fixed(45.0))
+        };
+        let now = self.clock.now();
+        let attempts = format!(
+            "Attempts: {} in the last {} min, {} since start, {} escalations",
+            self.tamper.recent(now, tamper.window()), tamper.window_minutes, self.tamper.total(), self.tamper.escalations(),
+        );
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Tamper Protection").size(18).height(30).center())
+                .push(toggler(tamper.enabled).label("Act on repeated attempts to go past the limit").on_toggle_maybe(self.unlocked(|enabled| Message::EditTamper(TamperEdit::Enabled(enabled)))))
+                .push(Row::new()
+                    .push(text("After"))
+                    .push(input(tamper.threshold, &draft.threshold, 1..=100, TamperEdit::Threshold, TamperEdit::SubmitThreshold))
+                    .push(text("attempts within"))
+                    .push(input(tamper.window_minutes, &draft.window, 1..=240, TamperEdit::Window, TamperEdit::SubmitWindow))
+                    .push(text("min"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(toggler(tamper.notify).label("Send a notification").on_toggle_maybe(self.unlocked(|notify| Message::EditTamper(TamperEdit::Notify(notify)))))
+                .push(Row::new()
+                    .push(toggler(tamper.mute).label("Mute for").on_toggle_maybe(self.unlocked(|mute| Message::EditTamper(TamperEdit::Mute(mute)))))
+                    .push(input(tamper.mute_minutes, &draft.mute_minutes, 1..=240, TamperEdit::MuteMinutes, TamperEdit::SubmitMuteMinutes))
+                    .push(text("min"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(Row::new()
+                    .push(toggler(tamper.lower).label("Lower the limit by").on_toggle_maybe(self.unlocked(|lower| Message::EditTamper(TamperEdit::Lower(lower)))))
+                    .push(input(tamper.lower_by, &draft.lower_by, 1..=100, TamperEdit::LowerBy, TamperEdit::SubmitLowerBy))
+                    .push(text("%"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(text(attempts))
+                .push_maybe(self.muted_until.filter(|&until| now < until).map(|until| {
+                    let remaining = (until - now).num_seconds();
+                    text(format!("Muted for another {}:{:02}", remaining / 60, remaining % 60))
+                }))
+                .push_maybe(self.tamper_message.as_ref().map(|message| text(message.clone())))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn device_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let safe_volume = self.settings.safe_volume.get(&self.output_device).copied();
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Output Device").size(18).height(30).center())
+                .push(text(if self.output_device.is_empty() {String::from("No output device")} else {self.output_device.clone()}))
+                .push(Row::new()
+                    .push(toggler(safe_volume.is_some()).label("When it becomes active, lower to").on_toggle_maybe(self.unlocked(Message::ToggleSafeVolume)))
+                    .push(
+                        NumericInput::new(safe_volume.unwrap_or(DEFAULT_SAFE_VOLUME), &self.safe_str)
+                            .style(inputs::themed(metrics))
+                            .error_style(inputs::error(metrics))
+                            .disabled(self.locked)
+                            .on_input(Message::ChangeSafeVolume)
+                            .on_submit(Message::SubmitSafeVolume)
+                            .buttons(false)
+                            .show_error(false)
+                            .width(Length::Fixed(45.0))
+                    )
+                    .push(text("%"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(Row::new()
+                    .push(toggler(self.settings.startup_volume.enabled).label("On app start, set volume to").on_toggle_maybe(self.unlocked(Message::ToggleStartupVolume)))
+                    .push(
+                        NumericInput::new(self.settings.startup_volume.percent, &self.startup_str)
+                            .style(inputs::themed(metrics))
+                            .error_style(inputs::error(metrics))
+                            .disabled(self.locked)
+                            .on_input(Message::ChangeStartupVolume)
+                            .on_submit(Message::SubmitStartupVolume)
+                            .buttons(false)
+                            .show_error(false)
+                            .width(Length::Fixed(45.0))
+                    )
+                    .push(text("%"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push_maybe(AMPLIFICATION_POSSIBLE.then(|| Row::new()
+                    .push(text("Refuse amplification above"))
+                    .push(
+                        NumericInput::new(self.settings.max_volume, &self.max_volume_str)
+                            .style(inputs::themed(metrics))
+                            .error_style(inputs::error(metrics))
+                            .disabled(self.locked)
+                            .range(100..=200)
+                            .on_input(Message::ChangeMaxVolume)
+                            .on_submit(Message::SubmitMaxVolume)
+                            .buttons(false)
+                            .show_error(false)
+                            .width(Length::Fixed(45.0))
+                    )
+                    .push(text("%"))
+                    .align_y(Alignment::Center).spacing(8)))
+                .push_maybe(self.over_amplified.map(|(peak, at)| {
+                    text(format!("Over-amplified to {}% at {}, brought back to {}%", peak, at.format("%H:%M"), self.settings.max_volume)).style(text::danger)
+                }))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn input_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let content = match (&self.input_device, self.input_volume) {
+            (Some(device), Some(volume)) => InputLimit::ALL.into_iter().fold(
+                Column::new()
+                    .push(text(device.clone()))
+                    .push(Row::new()
+                        .push(self.volume_slider(metrics, volume, 100, Message::InputVolChange))
+                        .push(text(self.level_label(volume)))
+                        .align_y(Alignment::Center).spacing(20)),
+                |column, limit| {
+                    let level = input_level(&self.settings, limit);
+                    column.push(Row::new()
+                        .push(toggler(level.enabled).label(limit.label()).on_toggle_maybe(self.unlocked(move |enabled| Message::ToggleInputLimit(limit, enabled))))
+                        .push(
+                            NumericInput::new(level.percent, &self.input_strs[limit.index()])
+                                .style(inputs::themed(metrics))
+                                .error_style(inputs::error(metrics))
+                                .disabled(self.locked)
+                                .on_input(move |input| Message::ChangeInputLimit(limit, input))
+                                .on_submit(move |percent| Message::SubmitInputLimit(limit, percent))
+                                .buttons(false)
+                                .show_error(false)
+                                .width(Length::Fixed(45.0))
+                        )
+                        .push(text("%"))
+                        .align_y(Alignment::Center).spacing(8))
+                },
+            ).spacing(10),
+            _ => Column::new().push(text("Microphone control isn't available on this system")),
+        };
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Microphone").size(18).height(30).center())
+                .push(content)
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn dose_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let daily = self.dosimeter.daily_percent();
+        let week = self.dosimeter.week().into_iter().fold(Row::new().spacing(10), |row, day| {
+            row.push(Column::new()
+                .push(progress_bar(0.0..=100.0, (day.dose * 100.0).min(100.0)).height(6))
+                .push(text(day.date.weekday().to_string()).size(12))
+                .push(text(format!("{:.0}%", day.dose * 100.0)).size(12))
+                .align_x(Alignment::Center).spacing(2).width(Length::Fill))
+        });
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Hearing Exposure").size(18).height(30).center())
+                .push(text(format!("Today's sound dose: {:.0}% of the daily allowance", daily)))
+                .push(progress_bar(0.0..=100.0, daily.min(100.0)).height(10))
+                .push(week)
+                .push(Row::new()
+                    .push(text("Standard"))
+                    .push(pick_list(self.locked_options(DoseStandard::ALL.to_vec(), Some(&self.dosimeter.standard)), Some(self.dosimeter.standard), |standard| self.unlocked_or_none(Message::ChangeDoseStandard(standard))))
+                    .align_y(Alignment::Center).spacing(10))
+                .push(toggler(self.settings.dosimeter.tighten).label("Tighten the limit as the budget runs out").on_toggle_maybe(self.unlocked(Message::ToggleDoseTighten)))
+                .push(Row::new()
+                    .push(text(format!("Loudness of {} at 100% (dB SPL)", if self.output_device.is_empty() {"output"} else {&self.output_device})).width(Length::Fill))
+                    .push(
+                        NumericInput::new(self.sensitivity().max_spl_db.round() as u8, &self.sensitivity_str)
+                            .style(inputs::themed(metrics))
+                            .error_style(inputs::error(metrics))
+                            .disabled(self.locked)
+                            .range(60..=130)
+                            .on_input(Message::ChangeSensitivity)
+                            .on_submit(Message::SubmitSensitivity)
+                            .buttons(false)
+                            .show_error(false)
+                            .width(Length::Fixed(50.0))
+                    )
+                    .align_y(Alignment::Center).spacing(10))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    pub fn theme(&self) -> Theme{
+        self.theme_choice.resolve(&self.system_theme, &self.custom_themes)
     }
 
     pub fn subscription(&self) -> Subscription<Message>{
@@ -713,10 +2091,633 @@ impl VolControl {
             iced::time::every(std::time::Duration::from_secs(5)).map(|_| Message::AutoLimiter),
             iced::time::every(std::time::Duration::from_millis(500)).map(|_| Message::SystemVolChange),
             iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::ClearError),
+            keyboard::on_key_press(|key, modifiers| match key {
+                keyboard::Key::Named(keyboard::key::Named::Tab) => Some(Message::FocusNext(modifiers.shift())),
+                _ => None,
+            }),
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::ReloadThemes),
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::PollRequests),
+            window::close_requests().map(Message::CloseRequested),
+            // Polled separately from the device list so a newly plugged-in device is caught within a second
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::CheckOutputDevice),
+            if self.limit_override.is_some() {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::OverrideTick)
+            } else {
+                Subscription::none()
+            },
+            if self.sleep_timer.is_some() {
+                iced::time::every(std::time::Duration::from_millis(250)).map(|_| Message::SleepTick)
+            } else {
+                Subscription::none()
+            },
+            if self.settings.schedule.enabled {
+                iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::ScheduleTick)
+            } else {
+                Subscription::none()
+            },
+            if self.theme_choice == ThemeChoice::System {
+                iced::time::every(std::time::Duration::from_secs(5)).map(|_| Message::DetectSystemTheme)
+            } else {
+                Subscription::none()
+            },
         ])
         
     }
 
+    fn schedule_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let mut devices = vec![ANY_DEVICE.to_owned()];
+        devices.extend(self.all_devices.iter().filter(|device| !device.is_empty()).cloned());
+        let rules = self.settings.schedule.rules.iter().zip(&self.rule_drafts).enumerate().fold(Column::new().spacing(15), |column, (index, (rule, draft))| {
+            let edit = move |edit| Message::EditRule(index, edit);
+            column.push(Column::new()
+                .push(Row::new()
+                    .push(text_input("Name", &rule.name).on_input_maybe(self.unlocked(move |name| edit(RuleEdit::Name(name))))
+                        .on_submit_maybe(self.unlocked(edit(RuleEdit::SubmitName))).style(inputs::themed(metrics)))
+                    .push(button(text("Remove")).on_press_maybe(self.unlocked(edit(RuleEdit::Remove))).style(buttons::themed(metrics)))
+                    .align_y(Alignment::Center).spacing(10))
+                .push(schedule::WEEK.iter().fold(Row::new().spacing(8), |row, &day| {
+                    row.push(checkbox(day.to_string(), rule.days.contains(&day)).on_toggle_maybe(self.unlocked(move |_| edit(RuleEdit::ToggleDay(day)))).size(14).text_size(12))
+                }))
+                .push(Row::new()
+                    .push(text("From"))
+                    .push(text_input("HH:MM", &draft.start).on_input_maybe(self.unlocked(move |input| edit(RuleEdit::Start(input))))
+                        .style(inputs::validated(metrics, schedule::parse_time(&draft.start).is_some())).width(Length::Fixed(60.0)))
+                    .push(text("to"))
+                    .push(text_input("HH:MM", &draft.end).on_input_maybe(self.unlocked(move |input| edit(RuleEdit::End(input))))
+                        .style(inputs::validated(metrics, schedule::parse_time(&draft.end).is_some())).width(Length::Fixed(60.0)))
+                    .push(toggler(matches!(rule.action, RuleAction::Limit(_))).label("Limit").on_toggle_maybe(self.unlocked(move |limited| edit(RuleEdit::Limited(limited)))))
+                    .push_maybe(if let RuleAction::Limit(percent) = rule.action {
+                        Some(NumericInput::new(percent, &draft.percent)
+                            .style(inputs::themed(metrics))
+                            .error_style(inputs::error(metrics))
+                            .disabled(self.locked)
+                            .on_input(move |input| edit(RuleEdit::Percent(input)))
+                            .on_submit(move |percent| edit(RuleEdit::SubmitPercent(percent)))
+                            .buttons(false)
+                            .show_error(false)
+                            .width(Length::Fixed(45.0)))
+                    } else {
+                        None
+                    })
+                    .align_y(Alignment::Center).spacing(10))
+                .push({
+                    let device = rule.device.clone().unwrap_or(ANY_DEVICE.to_owned());
+                    pick_list(self.locked_options(devices.clone(), Some(&device)), Some(device), move |device| edit(RuleEdit::Device(device)))
+                })
+                .spacing(8))
+        });
+        let active = match &self.active_rule {
+            Some(rule) => format!("Active rule: {} ({})", rule.name, rule.action),
+            None if self.settings.schedule.enabled => String::from("No rule active"),
+            None => String::from("Schedule is off"),
+        };
+        let ramp = &self.settings.ramp;
+        let draft = &self.ramp_draft;
+        let percent_input = |value, input, on_input: fn(String) -> RampEdit, on_submit: fn(u8) -> RampEdit| {
+            NumericInput::new(value, input)
+                .style(inputs::themed(metrics))
+                .error_style(inputs::error(metrics))
+                .disabled(self.locked)
+                .on_input(move |input| Message::EditRamp(on_input(input)))
+                .on_submit(move |percent| Message::EditRamp(on_submit(percent)))
+                .buttons(false)
+                .show_error(false)
+                .width(Length::Fixed(45.0))
+        };
+        let time_input = |input, on_input: fn(String) -> RampEdit| {
+            text_input("HH:MM", input)
+                .on_input_maybe(self.unlocked(move |input| Message::EditRamp(on_input(input))))
+                .style(inputs::validated(metrics, schedule::parse_time(input).is_some()))
+                .width(Length::Fixed(60.0))
+        };
+        let wind_down = Column::new()
+            .push(toggler(ramp.enabled).label("Evening wind-down (while the limiter is on)").on_toggle_maybe(self.unlocked(|enabled| Message::EditRamp(RampEdit::Enabled(enabled)))))
+            .push(Row::new()
+                .push(text("From"))
+                .push(percent_input(ramp.from, &draft.from, RampEdit::From, RampEdit::SubmitFrom))
+                .push(text("at"))
+                .push(time_input(&draft.start, RampEdit::Start))
+                .push(text("to"))
+                .push(percent_input(ramp.to, &draft.to, RampEdit::To, RampEdit::SubmitTo))
+                .push(text("at"))
+                .push(time_input(&draft.end, RampEdit::End))
+                .align_y(Alignment::Center).spacing(8))
+            .push(Row::new()
+                .push(text("Hold until"))
+                .push(time_input(&draft.until, RampEdit::Until))
+                .push_maybe(ramp.ceiling_at(self.clock.now()).map(|ceiling| text(format!("Wind-down ceiling: {}%", ceiling))))
+                .align_y(Alignment::Center).spacing(8))
+            .spacing(8);
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Schedule").size(18).height(30).center())
+                .push(wind_down)
+                .push(toggler(self.settings.schedule.enabled).label("Follow the schedule").on_toggle_maybe(self.unlocked(Message::ToggleSchedule)))
+                .push(text(active))
+                .push(rules)
+                .push(Row::new()
+                    .push(button(text("Add Rule")).on_press_maybe(self.unlocked(Message::AddRule)).style(buttons::themed(metrics)))
+                    .push(button(text("Add Quiet Hours")).on_press_maybe(self.unlocked(Message::AddQuietHours)).style(buttons::themed(metrics)))
+                    .spacing(10))
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn sleep_view(&self, metrics: theme_file::Metrics) -> Element<'_, Message> {
+        let sleep = &self.settings.sleep;
+        let draft = &self.sleep_draft;
+        let input = |value, input, range, on_input: fn(String) -> SleepEdit, on_submit: fn(u8) -> SleepEdit| {
+            NumericInput::new(value, input)
+                .style(inputs::themed(metrics))
+                .error_style(inputs::error(metrics))
+                .range(range)
+                .on_input(move |input| Message::EditSleep(on_input(input)))
+                .on_submit(move |value| Message::EditSleep(on_submit(value)))
+                .buttons(false)
+                .show_error(false)
+                .width(Length::Fixed(45.0))
+        };
+        let now = Instant::now();
+        let status = match &self.sleep_timer {
+            Some(timer) => {
+                let remaining = timer.remaining(now).as_secs();
+                format!("{} in {}:{:02}", if timer.is_fading(now) {"Fading out, done"} else {"Sleeping"}, remaining / 60, remaining % 60)
+            },
+            None => String::from("No sleep timer running"),
+        };
+        HovContainer::new()
+            .push(Column::new()
+                .push(text("Sleep Timer").size(18).height(30).center())
+                .push(Row::new()
+                    .push(text("Sleep in"))
+                    .push(input(sleep.minutes, &draft.minutes, 1..=240, SleepEdit::Minutes, SleepEdit::SubmitMinutes))
+                    .push(text("min, fading over"))
+                    .push(input(sleep.fade_minutes, &draft.fade, 0..=240, SleepEdit::Fade, SleepEdit::SubmitFade))
+                    .push(text("min"))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(Row::new()
+                    .push(text("Down to"))
+                    .push(input(sleep.floor, &draft.floor, 0..=100, SleepEdit::Floor, SleepEdit::SubmitFloor))
+                    .push(text("%, then"))
+                    .push(pick_list(AfterSleep::ALL, Some(sleep.after), |after| Message::EditSleep(SleepEdit::After(after))))
+                    .align_y(Alignment::Center).spacing(8))
+                .push(text(status))
+                .push(if self.sleep_timer.is_some() {
+                    button(text("Cancel")).on_press_maybe(self.unlocked(Message::CancelSleep)).style(buttons::themed(metrics))
+                } else {
+                    button(text("Start")).on_press(Message::StartSleep(*sleep)).style(buttons::themed(metrics))
+                })
+                .align_x(Alignment::Center).spacing(10).padding(20).width(Length::Fill)
+            )
+            .on_hover(Message::None).on_exit(Message::None).focusable(true)
+            .style(hov_container_row::palette_style(metrics.container_border, metrics.container_radius))
+            .into()
+    }
+
+    fn edit_ramp(&mut self, edit: RampEdit) {
+        let (ramp, draft) = (&mut self.settings.ramp, &mut self.ramp_draft);
+        let time = |input: &str, time: &mut chrono::NaiveTime| {
+            if let Some(parsed) = schedule::parse_time(input) {
+                *time = parsed;
+            }
+        };
+        match edit {
+            RampEdit::Enabled(enabled) => ramp.enabled = enabled,
+            RampEdit::Start(input) => {
+                time(&input, &mut ramp.start);
+                draft.start = input;
+            },
+            RampEdit::End(input) => {
+                time(&input, &mut ramp.end);
+                draft.end = input;
+            },
+            RampEdit::Until(input) => {
+                time(&input, &mut ramp.until);
+                draft.until = input;
+            },
+            RampEdit::From(input) => draft.from = input,
+            RampEdit::SubmitFrom(percent) => {
+                ramp.from = percent;
+                draft.from = percent.to_string();
+            },
+            RampEdit::To(input) => draft.to = input,
+            RampEdit::SubmitTo(percent) => {
+                ramp.to = percent;
+                draft.to = percent.to_string();
+            },
+        }
+    }
+
+    fn edit_rule(&mut self, index: usize, edit: RuleEdit) {
+        let (Some(rule), Some(draft)) = (self.settings.schedule.rules.get_mut(index), self.rule_drafts.get_mut(index)) else {
+            return;
+        };
+        match edit {
+            RuleEdit::Name(name) => rule.name = name,
+            RuleEdit::SubmitName => {},
+            RuleEdit::ToggleDay(day) => {
+                if let Some(position) = rule.days.iter().position(|&selected| selected == day) {
+                    rule.days.remove(position);
+                } else {
+                    rule.days.push(day);
+                }
+            },
+            RuleEdit::Start(input) => {
+                if let Some(time) = schedule::parse_time(&input) {
+                    rule.start = time;
+                }
+                draft.start = input;
+            },
+            RuleEdit::End(input) => {
+                if let Some(time) = schedule::parse_time(&input) {
+                    rule.end = time;
+                }
+                draft.end = input;
+            },
+            RuleEdit::Limited(limited) => {
+                rule.action = if limited {
+                    RuleAction::Limit(draft.percent.parse().unwrap_or(self.percent).min(100))
+                } else {
+                    RuleAction::Unlimited
+                };
+            },
+            RuleEdit::Percent(input) => draft.percent = input,
+            RuleEdit::SubmitPercent(percent) => {
+                rule.action = RuleAction::Limit(percent);
+                draft.percent = percent.to_string();
+            },
+            RuleEdit::Device(device) => rule.device = if device == ANY_DEVICE {None} else {Some(device)},
+            RuleEdit::Remove => {
+                self.settings.schedule.rules.remove(index);
+                self.rule_drafts.remove(index);
+            },
+        }
+    }
+
+    // Devices without a stored profile are guessed from their name, the device picked for the auto limiter counts as headphones
+    fn sensitivity(&self) -> SensitivityProfile {
+        if let Some(&max_spl_db) = self.settings.sensitivity.get(&self.output_device) {
+            SensitivityProfile { max_spl_db }
+        } else if self.device.as_ref().is_some_and(|device| !device.is_empty() && *device == self.output_device) {
+            SensitivityProfile::for_class(DeviceClass::Headphones)
+        } else {
+            SensitivityProfile::for_class(device::classify(&self.output_device))
+        }
+    }
+
+    fn ceiling(&self) -> u8 {
+        if self.muted_until.is_some_and(|until| self.clock.now() < until) {
+            return 0;
+        }
+        // A granted override stands in for every other ceiling until it runs out
+        if let Some(percent) = self.limit_override.and_then(|grant| grant.ceiling(self.clock.now())) {
+            return percent;
+        }
+        let budget = if self.settings.dosimeter.tighten {
+            self.dosimeter.budget_ceiling(&self.sensitivity())
+        } else {
+            None
+        };
+        let ramp = self.settings.ramp.ceiling_at(self.clock.now());
+        [budget, ramp].into_iter().flatten().fold(self.percent, u8::min)
+    }
+
+    // The usual ceiling comes back, the engine fades the volume down to it if needed
+    fn end_override(&mut self) {
+        if self.limit_override.take().is_some() {
+            self.audit(Event::OverrideEnded);
+        }
+        if let Err(error) = Override::clear() {
+            eprintln!("Failed to clear the temporary limit: {}", error);
+        }
+        self.sync_engine();
+    }
+
+    // Runs the actions picked for repeated attempts to get past the ceiling
+    fn escalate(&mut self) {
+        let tamper = self.settings.tamper;
+        let message = format!("The volume was pushed past the limit {} times within {} minutes", tamper.threshold, tamper.window_minutes);
+        self.audit(Event::Escalation { attempts: tamper.threshold, window_minutes: tamper.window_minutes });
+        if tamper.notify && let Err(error) = notify::notify("Volume limit", &message) {
+            eprintln!("Failed to send notification: {}", error);
+        }
+        self.tamper_message = Some(message);
+        if tamper.mute {
+            self.muted_until = Some(self.clock.now() + chrono::Duration::minutes(tamper.mute_minutes as i64));
+            self.send_command(VolumeCommand::SetMute(Some(true)));
+            self.controls().show(0);
+        }
+        // Lowered in place rather than through the limiter off and on, so the saved limit never passes through off
+        if tamper.lower {
+            self.set_percent(self.percent.saturating_sub(tamper.lower_by), false);
+        }
+        self.sync_engine();
+    }
+
+    fn edit_tamper(&mut self, edit: TamperEdit) {
+        let (tamper, draft) = (&mut self.settings.tamper, &mut self.tamper_draft);
+        match edit {
+            TamperEdit::Enabled(enabled) => tamper.enabled = enabled,
+            TamperEdit::Threshold(input) => draft.threshold = input,
+            TamperEdit::SubmitThreshold(threshold) => {
+                tamper.threshold = threshold;
+                draft.threshold = threshold.to_string();
+            },
+            TamperEdit::Window(input) => draft.window = input,
+            TamperEdit::SubmitWindow(minutes) => {
+                tamper.window_minutes = minutes;
+                draft.window = minutes.to_string();
+            },
+            TamperEdit::Notify(notify) => tamper.notify = notify,
+            TamperEdit::Mute(mute) => tamper.mute = mute,
+            TamperEdit::MuteMinutes(input) => draft.mute_minutes = input,
+            TamperEdit::SubmitMuteMinutes(minutes) => {
+                tamper.mute_minutes = minutes;
+                draft.mute_minutes = minutes.to_string();
+            },
+            TamperEdit::Lower(lower) => tamper.lower = lower,
+            TamperEdit::LowerBy(input) => draft.lower_by = input,
+            TamperEdit::SubmitLowerBy(percent) => {
+                tamper.lower_by = percent;
+                draft.lower_by = percent.to_string();
+            },
+        }
+    }
+
+    fn update_limits(&mut self) {
+        self.sync_engine();
+        self.save_settings();
+    }
+
+    // Pushes the current limits to the engine, which only runs while one of them has something to enforce
+    fn sync_engine(&mut self) {
+        self.limits.set_ceiling(self.limiter.then(|| self.ceiling()));
+        self.limits.set_max_volume(AMPLIFICATION_POSSIBLE.then_some(self.settings.max_volume));
+        self.limits.set_slew_rate(self.settings.slew.rate());
+        self.limits.set_floor(self.settings.floor.level());
+        self.limits.set_lock(self.settings.lock.level());
+        self.limits.set_allow_mute(self.settings.allow_mute);
+        match (self.limits.is_active(), self.engine.take()) {
+            (true, None) => self.engine = Some(Engine::start(Arc::clone(&self.limits), Direction::Output)),
+            (false, Some(engine)) => engine.stop(),
+            (_, engine) => self.engine = engine,
+        }
+        // There's no max volume for input, an amplified microphone is only held down by the input ceiling and isn't reported
+        let input = self.settings.input;
+        self.input_limits.set_ceiling(input.ceiling.level());
+        self.input_limits.set_floor(input.floor.level());
+        self.input_limits.set_lock(input.lock.level());
+        let input_active = self.input_limits.is_active() && self.input_volume.is_some();
+        match (input_active, self.input_engine.take()) {
+            (true, None) => self.input_engine = Some(Engine::start(Arc::clone(&self.input_limits), Direction::Input)),
+            (false, Some(engine)) => engine.stop(),
+            (_, engine) => self.input_engine = engine,
+        }
+    }
+
+    fn record_dose(&mut self) {
+        let now = Instant::now();
+        let elapsed = self.dose_sampled.map_or(Duration::ZERO, |last| (now - last).min(MAX_DOSE_SAMPLE));
+        self.dose_sampled = Some(now);
+        self.dosimeter.record(self.clock.now().date(), self.sensitivity().spl_at(self.volume()), elapsed);
+        // The dose, ramp and temporary limit move the ceiling over time, the engine only hears about actual changes
+        if self.limits.ceiling() != self.limiter.then(|| self.ceiling()) {
+            self.sync_engine();
+        }
+        if now - self.dose_saved >= DOSE_SAVE_INTERVAL {
+            self.dose_saved = now;
+            if let Err(error) = self.dosimeter.save() {
+                eprintln!("Failed to save sound dose: {}", error);
+            }
+        }
+    }
+
+    fn record_history(&mut self, clamped: bool) {
+        let now = Instant::now();
+        let elapsed = self.history_sampled.map_or(Duration::ZERO, |last| (now - last).min(MAX_DOSE_SAMPLE));
+        self.history_sampled = Some(now);
+        let ceiling = self.limiter.then(|| self.ceiling());
+        if let Err(error) = self.history.record(self.clock.now(), elapsed, self.volume(), ceiling, clamped, &self.output_device) {
+            eprintln!("Failed to save volume history: {}", error);
+        }
+    }
+
+    fn refresh_input(&mut self) {
+        self.input_volume = match self.send_command(VolumeCommand::GetInputVol(None)) {
+            // The slider stops at 100%, the input engine still sees amplified levels and caps them at the input ceiling
+            VolumeCommand::GetInputVol(volume) => volume.map(|volume| volume.percent_u8().min(100)),
+            _ => None,
+        };
+        if let VolumeCommand::GetDefaultInputDevice(device) = self.send_command(VolumeCommand::GetDefaultInputDevice(None)) {
+            self.input_device = device;
+        }
+    }
+
+    // Slider over positions on the volume curve, reporting the volume they map to up to `max`
+    fn volume_slider<'a>(&self, metrics: theme_file::Metrics, volume: u8, max: u8, on_change: impl Fn(u8) -> Message + 'a) -> Element<'a, Message> {
+        let curve = self.settings.curve;
+        slider(0..=curve.to_position(max), curve.to_position(volume), move |position| on_change(curve.to_volume(position).min(max)))
+            .style(sliders::themed(metrics))
+            .into()
+    }
+
+    // Handler for a protected control, None shows it as disabled while locked
+    fn unlocked<T>(&self, handler: T) -> Option<T> {
+        (!self.locked).then_some(handler)
+    }
+
+    // Radios and pick lists can't be disabled, while locked their choices do nothing instead
+    fn unlocked_or_none(&self, message: Message) -> Message {
+        self.unlocked(message).unwrap_or(Message::None)
+    }
+
+    // Pick list options, narrowed to the current choice while locked so the list shows nothing else to pick
+    fn locked_options<T: PartialEq>(&self, options: Vec<T>, selected: Option<&T>) -> Vec<T> {
+        if self.locked {
+            options.into_iter().filter(|option| Some(option) == selected).collect()
+        } else {
+            options
+        }
+    }
+
+    fn pin_matches(&self, pin: Option<&str>) -> bool {
+        match (&self.pin_hash, pin) {
+            (Some(hash), Some(pin)) => pin::verify(hash, pin),
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    }
+
+    fn proof_matches(&mut self, envelope: &ipc::Envelope) -> bool {
+        let now = ipc::unix_time();
+        match (&self.pin_hash, &envelope.proof) {
+            (Some(hash), Some(proof)) => proof.verify(envelope.request, hash, now) && self.replays.first_use(proof, now),
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    }
+
+    // While locked the limit can still be lowered, but not raised
+    fn limit_range(&self) -> RangeInclusive<u8> {
+        if self.locked {0..=self.percent} else {0..=100}
+    }
+
+    fn level_label(&self, volume: u8) -> String {
+        if self.settings.show_db {
+            Volume::from(volume).db_label()
+        } else {
+            volume.to_string()
+        }
+    }
+
+    fn refresh_channels(&mut self) {
+        self.channels = match self.send_command(VolumeCommand::GetChannels(None)) {
+            VolumeCommand::GetChannels(Some(channels)) => channels,
+            _ => Vec::new(),
+        };
+        self.balance = balance::balance_of(&self.channels);
+    }
+
+    fn refresh_output_device(&mut self) {
+        if let VolumeCommand::GetDefaultDevice(Some(device)) = self.send_command(VolumeCommand::GetDefaultDevice(None)) && device != self.output_device {
+            self.output_device = device;
+            self.limits.device_switched();
+            self.enter_device();
+        }
+    }
+
+    // Runs whenever a device becomes the output, including the one active at startup
+    fn enter_device(&mut self) {
+        self.sensitivity_str = self.sensitivity().max_spl_db.round().to_string();
+        self.safe_str = self.settings.safe_volume.get(&self.output_device).copied().unwrap_or(DEFAULT_SAFE_VOLUME).to_string();
+        // The OS may have restored a different level for the new device, so read it fresh
+        if let VolumeCommand::GetVol(Some(vol)) = self.send_command(VolumeCommand::GetVol(None)) {
+            self.controls().show(vol.percent_u8());
+        }
+        // Bring back the level last picked on this device, then cap it like any other change
+        let target = self.capped(self.volumes.get(&self.output_device).and_then(|controls| controls.chosen).unwrap_or(self.volume()));
+        if target != self.volume() {
+            self.set_volume(target);
+        }
+    }
+
+    // Levels the app sets by itself stay under the ceiling and the device's safe volume, rather than waiting for the engine
+    fn capped(&self, volume: u8) -> u8 {
+        let volume = if self.limiter {volume.min(self.ceiling())} else {volume};
+        match self.settings.safe_volume.get(&self.output_device) {
+            Some(&safe_volume) => volume.min(safe_volume),
+            None => volume,
+        }
+    }
+
+    fn volume(&self) -> u8 {
+        self.volumes.get(&self.output_device).map_or(0, |controls| controls.vol)
+    }
+
+    fn vol_str(&self) -> &str {
+        self.volumes.get(&self.output_device).map_or("", |controls| controls.vol_str.as_str())
+    }
+
+    fn controls(&mut self) -> &mut VolumeControls {
+        self.volumes.get_mut(&self.output_device)
+    }
+
+    // Writes a level to the system without taking it as the user's choice
+    fn set_volume(&mut self, volume: u8) {
+        self.limits.expect_write(volume);
+        let controls = self.controls();
+        controls.show(volume);
+        controls.set = Some(volume);
+        self.send_command(VolumeCommand::SetVol(Some(Volume::from(volume))));
+    }
+
+    fn choose_volume(&mut self, volume: u8) {
+        self.set_volume(volume);
+        self.controls().chosen = Some(volume);
+        self.save_volumes();
+    }
+
+    fn save_volumes(&self) {
+        if let Err(error) = self.volumes.save() {
+            eprintln!("Failed to save device volumes: {}", error);
+        }
+    }
+
+    fn save_settings(&mut self) {
+        // A settings file that failed to load is left for the user to fix, changes only last until the app quits
+        if self.settings_error.is_some() {
+            return;
+        }
+        if let Err(error) = self.settings.save() {
+            eprintln!("Failed to save settings: {}", error);
+        }
+        for change in audit::settings_changes(&self.saved_settings, &self.settings) {
+            self.audit(change);
+        }
+        self.saved_settings = self.settings.clone();
+    }
+
+    // Catches a rule name that was typed but never submitted, when focus or the tab moves away or the app quits
+    fn save_typed(&mut self) {
+        if self.settings != self.saved_settings {
+            self.save_settings();
+        }
+    }
+
+    fn set_percent(&mut self, percent: u8, manual: bool) {
+        if percent != self.percent {
+            self.audit(Event::Setting { name: String::from("limit"), value: percent.to_string() });
+        }
+        self.percent = percent;
+        self.percent_str = self.percent.to_string();
+        self.save_limit();
+        if !manual {
+            self.sel_lim.replace(BuiltIn::from_percent(self.percent));
+        }
+    }
+
+    // Kept in the settings so a restart doesn't undo it, the changes themselves are logged as they happen
+    fn save_limit(&mut self) {
+        let limit = LevelSettings { enabled: self.limiter, percent: self.percent };
+        if self.settings.limit != limit {
+            self.settings.limit = limit;
+            self.saved_settings.limit = limit;
+            self.save_settings();
+        }
+    }
+
+    fn audit(&self, event: Event) {
+        audit::record(self.clock.now(), event);
+    }
+
+    // Only flips with a cause are logged, along with the schedule rule behind them
+    fn log_limiter(&self, cause: Option<Cause>) {
+        if let Some(cause) = cause {
+            let rule = self.active_rule.as_ref().filter(|_| cause == Cause::Schedule).map(|rule| rule.name.clone());
+            self.audit(Event::Limiter { enabled: self.limiter, cause, rule });
+        }
+    }
+
+    fn apply_percent(&mut self, percent: u8, limit: bool, manual: bool) -> Task<Message> {
+        if limit && self.limiter {
+            Task::batch(vec![
+                Task::perform(async {}, move |_| Message::DisableLimit(None)),
+                Task::perform(async {}, move |_| Message::SetLimit(percent)),
+                Task::perform(async {}, move |_| Message::EnableLimit(None)),
+            ])
+        } else if limit {
+            self.set_percent(percent, manual);
+            Task::none()
+        } else {
+            let volume = if self.limiter {percent.min(self.ceiling())} else {percent};
+            self.choose_volume(volume);
+            Task::none()
+        }
+    }
+
     fn send_command(&mut self, command: VolumeCommand) -> VolumeCommand {
         self.cmd_tx.send(command).expect("Failed to send command");
         if let Ok(command) = self.cmd_rx.recv() {
@@ -737,41 +2738,175 @@ impl VolControl {
 
 }
 
-fn enable_limiter(percent: u8, rx: Receiver<bool>) -> JoinHandle<()> {
-    thread::spawn(move || {
-        let mut limit;
-        if let Ok(status) = rx.recv() {
-            println!("limit is :{}", status);
-            limit = status;
-        } else {
-            limit = true;
-        }
-        while limit {
-            // println!("Blocking!");
-            // println!("Current System volume is: {}", get_system_volume());
-            if get_system_volume() > percent {
-                set_system_volume(percent);
-            }
-            thread::sleep(Duration::from_millis(100));
+// Changes that loosen the limits or the rules behind them, refused from the GUI while locked
+fn is_protected(message: &Message) -> bool {
+    matches!(message,
+        Message::OnToggle(false) | Message::ChangeLimitSel(_) | Message::ChangeAutoLimiter(_) | Message::OnPick(_)
+        | Message::ChangeDoseStandard(_) | Message::ToggleDoseTighten(_) | Message::SubmitSensitivity(_)
+        | Message::ToggleSchedule(_) | Message::AddRule | Message::AddQuietHours | Message::EditRule(..) | Message::EditRamp(_)
+        | Message::CancelSleep
+        | Message::ToggleSlew(_) | Message::SubmitSlew(_) | Message::ToggleFloor(_) | Message::SubmitFloor(_)
+        | Message::ToggleAllowMute(_) | Message::ToggleLock(_) | Message::SubmitLock(_)
+        | Message::ToggleSafeVolume(_) | Message::SubmitSafeVolume(_) | Message::ToggleStartupVolume(_) | Message::SubmitStartupVolume(_)
+        | Message::ToggleInputLimit(..) | Message::SubmitInputLimit(..) | Message::SubmitMaxVolume(_)
+        | Message::SetPin | Message::RemovePin | Message::GrantOverride(..) | Message::EndOverride | Message::EditTamper(_)
+    )
+}
 
-            if let Ok(status) = rx.recv_timeout(Duration::from_millis(10)) {
-                println!("limit is :{}", status);
-                limit = status;
-            }
-        }
-        println!("thread ended");
-    })
+// Removing or breaking config.toml doesn't turn the limiter off while the settings are locked, and a granted
+// temporary limit needs it running
+fn limiter_at_start(settings: &Settings, limit_override: Option<Override>, pin_set: bool, settings_loaded: bool) -> bool {
+    settings.limit.enabled || limit_override.is_some() || (pin_set && !settings_loaded)
+}
+
+// A limiter restored from the settings or kept on by the lock isn't handed to the auto limiter, which would turn it
+// off again while no device is picked
+fn auto_limiter_at_start(limiter: bool, pin_set: bool) -> bool {
+    !limiter && !pin_set
+}
+
+fn startup_tasks() -> Task<Message> {
+    let tasks = Task::perform(async {}, |_| Message::DetectSystemTheme);
+    #[cfg(target_os = "linux")]
+    let tasks = Task::batch([tasks, Task::perform(vol_limiter::tray::spawn(), |_| Message::None)]);
+    tasks
+}
+
+fn hours_minutes(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{} h {:02} min", minutes / 60, minutes % 60)
+}
+
+fn input_level(settings: &Settings, limit: InputLimit) -> LevelSettings {
+    match limit {
+        InputLimit::Ceiling => settings.input.ceiling,
+        InputLimit::Floor => settings.input.floor,
+        InputLimit::Lock => settings.input.lock,
+    }
 }
 
-fn disable_limiter(tx:Sender<bool>) {
-    tx.send(false).unwrap();
+fn input_level_mut(settings: &mut Settings, limit: InputLimit) -> &mut LevelSettings {
+    match limit {
+        InputLimit::Ceiling => &mut settings.input.ceiling,
+        InputLimit::Floor => &mut settings.input.floor,
+        InputLimit::Lock => &mut settings.input.lock,
+    }
 }
 
 fn main() -> iced::Result{
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match cli::run(&args) {
+            Ok(output) => {
+                println!("{}", output);
+                return Ok(());
+            },
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(2);
+            },
+        }
+    }
     // get_sound_devices();
     let (process_tx, cmd_rx) = mpsc::channel();
     let (cmd_tx, process_rx) = mpsc::channel();
     let _cmd_handler = command_handler(process_tx, process_rx);
-    iced::application("Volume Limiter", VolControl::update, VolControl::view).theme(VolControl::theme).subscription(VolControl::subscription).window_size(Size{width:550.0, height:900.0}).run_with(|| { (VolControl::new(cmd_tx, cmd_rx), Task::none()) })
+    let settings = Settings::load();
+    if let Err(error) = &settings {
+        eprintln!("Not using the settings file: {}", error);
+    }
+    // Fonts can't be swapped on a running application, so they come from the theme selected at startup
+    let custom_themes = theme_file::load_themes();
+    let startup_theme = settings.as_ref().ok().and_then(|settings| ThemeChoice::from_name(&settings.theme).custom(&custom_themes).cloned());
+    let defaults = AppSettings::default();
+    let app_settings = AppSettings {
+        default_font: startup_theme.as_ref().and_then(CustomTheme::font).unwrap_or(defaults.default_font),
+        default_text_size: startup_theme.as_ref().and_then(|theme| theme.text_size).map(Pixels).unwrap_or(defaults.default_text_size),
+        ..defaults
+    };
+    iced::application("Volume Limiter", VolControl::update, VolControl::view).settings(app_settings).theme(VolControl::theme).subscription(VolControl::subscription).window_size(Size{width:550.0, height:900.0}).exit_on_close_request(false).run_with(|| { (VolControl::new(cmd_tx, cmd_rx, settings), startup_tasks()) })
     // Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A restart where the picked device is gone, the case the auto limiter turns the limiter off for
+    fn restarted(settings: &Settings, limit_override: Option<Override>, pin_set: bool) -> VolControl {
+        let limiter = limiter_at_start(settings, limit_override, pin_set, true);
+        VolControl {
+            limiter,
+            auto_autolimiter: auto_limiter_at_start(limiter, pin_set),
+            devices: vec![String::from("speakers")],
+            limit_override,
+            locked: pin_set,
+            ..VolControl::default()
+        }
+    }
+
+    fn grant() -> Option<Override> {
+        Some(Override::new(60, 30, SystemClock.now()))
+    }
+
+    #[test]
+    fn startup_volume_is_capped_by_the_ceiling_and_safe_volume() {
+        let mut control = restarted(&Settings { limit: LevelSettings { enabled: true, percent: 30 }, ..Settings::default() }, None, false);
+        control.percent = 30;
+        assert_eq!(control.capped(50), 30);
+        control.settings.safe_volume.insert(control.output_device.clone(), 25);
+        assert_eq!(control.capped(50), 25);
+        control.limiter = false;
+        control.settings.safe_volume.clear();
+        assert_eq!(control.capped(50), 50);
+    }
+
+    #[test]
+    fn restored_limiter_stays_on() {
+        let settings = Settings { limit: LevelSettings { enabled: true, percent: 30 }, ..Settings::default() };
+        let mut control = restarted(&settings, None, false);
+        assert!(control.limiter);
+        assert!(control.auto_limiter().is_none());
+    }
+
+    #[test]
+    fn restored_override_turns_the_limiter_on_and_keeps_it_on() {
+        let mut control = restarted(&Settings::default(), grant(), false);
+        assert!(control.limiter);
+        assert!(control.auto_limiter().is_none());
+        assert_eq!(control.ceiling(), 60);
+    }
+
+    #[test]
+    fn granted_override_is_left_alone_by_the_auto_limiter() {
+        let mut control = restarted(&Settings::default(), None, false);
+        control.auto_autolimiter = true;
+        control.limiter = true;
+        control.limit_override = grant();
+        assert!(control.auto_limiter().is_none());
+        control.limit_override = None;
+        assert!(matches!(control.auto_limiter(), Some(Message::DisableLimit(Some(Cause::AutoLimiter)))));
+    }
+
+    #[test]
+    fn lock_keeps_the_limiter_on_without_settings() {
+        let limiter = limiter_at_start(&Settings::default(), None, true, false);
+        assert!(limiter);
+        assert!(!limiter_at_start(&Settings::default(), None, false, false));
+    }
+
+    #[test]
+    fn auto_limiter_never_turns_off_a_locked_limiter() {
+        let mut control = restarted(&Settings::default(), None, true);
+        control.auto_autolimiter = true;
+        control.limiter = true;
+        assert!(control.auto_limiter().is_none());
+    }
+
+    #[test]
+    fn auto_limiter_still_runs_when_nothing_was_restored() {
+        let mut control = restarted(&Settings::default(), None, false);
+        assert!(control.auto_autolimiter);
+        control.device = Some(String::from("speakers"));
+        assert!(matches!(control.auto_limiter(), Some(Message::EnableLimit(Some(Cause::AutoLimiter)))));
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fs, io, path::PathBuf};
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use blake2::{digest::{KeyInit, Mac}, Blake2b512, Blake2bMac512, Digest};
+
+use crate::config::{self, Settings};
+
+pub const MIN_LENGTH: usize = 4;
+// Kept apart from config.toml, so deleting or breaking the settings doesn't lift the lock
+const PIN_FILE: &str = "pin";
+
+// Parental lock PINs are only ever stored as an Argon2 PHC string, which carries its own random salt
+pub fn hash(pin: &str) -> Result<String, String> {
+    if pin.chars().count() < MIN_LENGTH {
+        return Err(format!("the PIN needs at least {} characters", MIN_LENGTH));
+    }
+    if pin.chars().any(char::is_control) {
+        return Err(String::from("the PIN can't contain tabs or line breaks"));
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|error| format!("failed to hash the PIN: {}", error))
+}
+
+fn path() -> Option<PathBuf> {
+    config::state_dir().map(|dir| dir.join(PIN_FILE))
+}
+
+// A file that exists but can't be read keeps the app locked, as a hash nothing verifies against
+pub fn load_hash() -> Option<String> {
+    match path().map(fs::read_to_string) {
+        Some(Ok(hash)) => Some(hash.trim().to_owned()),
+        Some(Err(error)) if error.kind() != io::ErrorKind::NotFound => {
+            eprintln!("Failed to read the PIN: {}", error);
+            Some(String::new())
+        },
+        _ => migrate(),
+    }
+}
+
+// Earlier versions kept the hash in config.toml, it moves over the first time it's looked for.
+// Saving the settings again drops it from there, so removing the PIN later can't bring it back.
+fn migrate() -> Option<String> {
+    let settings = Settings::load().ok()?;
+    let hash = settings.pin_hash.clone()?;
+    if let Err(error) = save_hash(Some(&hash)).and_then(|_| settings.save()) {
+        eprintln!("Failed to move the PIN out of the settings: {}", error);
+    }
+    Some(hash)
+}
+
+// None removes the PIN
+pub fn save_hash(hash: Option<&str>) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+    match hash {
+        Some(hash) => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, hash)
+        },
+        None => match fs::remove_file(path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        },
+    }
+}
+
+// A stored hash that doesn't parse never verifies
+pub fn verify(hash: &str, pin: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(pin.as_bytes(), &hash).is_ok())
+}
+
+// Requests from the command line carry this keyed hash instead of the PIN. It is keyed with the stored hash, which
+// only the PIN could have unlocked on the sending side, so it is no more secret than the pin file itself
+fn mac(hash: &str) -> Blake2bMac512 {
+    let key = Blake2b512::digest(hash.as_bytes());
+    <Blake2bMac512 as KeyInit>::new_from_slice(&key).expect("a 64 byte key fits BLAKE2b")
+}
+
+pub fn sign(hash: &str, message: &str) -> String {
+    to_hex(&mac(hash).chain_update(message).finalize().into_bytes())
+}
+
+// Compared in constant time
+pub fn check(hash: &str, message: &str, signature: &str) -> bool {
+    from_hex(signature).is_some_and(|signature| mac(hash).chain_update(message).verify_slice(&signature).is_ok())
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_verifies_only_the_same_pin() {
+        let hash = hash("1234").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify(&hash, "1234"));
+        assert!(!verify(&hash, "1235"));
+        assert!(!verify(&hash, ""));
+    }
+
+    #[test]
+    fn same_pin_gets_a_new_salt_each_time() {
+        assert_ne!(hash("correct horse").unwrap(), hash("correct horse").unwrap());
+    }
+
+    #[test]
+    fn short_pins_and_control_characters_are_refused() {
+        assert!(hash("123").is_err());
+        assert!(hash("12\t34").is_err());
+        assert!(hash("12\n34").is_err());
+        // Counted in characters, not bytes
+        assert!(hash("äöü").is_err());
+        assert!(hash("äöüß").is_ok());
+    }
+
+    #[test]
+    fn unreadable_hashes_never_verify() {
+        assert!(!verify("", "1234"));
+        assert!(!verify("not a hash", "1234"));
+    }
+
+    #[test]
+    fn signatures_check_only_with_the_same_hash_and_message() {
+        let hash = hash("1234").unwrap();
+        let signature = sign(&hash, "lock");
+        assert_eq!(signature.len(), 128);
+        assert!(check(&hash, "lock", &signature));
+        assert!(!check(&hash, "unlock", &signature));
+        assert!(!check(&super::hash("1234").unwrap(), "lock", &signature));
+        assert!(!check(&hash, "lock", &signature[..126]));
+        assert!(!check(&hash, "lock", "not hex"));
+    }
+}
@@ -0,0 +1,212 @@
+
+use std::fmt::{self, Display};
+
+use iced::{border::Radius, Color, Theme};
+
+use theme_file::{CustomTheme, Metrics};
+
+pub mod theme_file;
+
+pub fn get_rgb_color(r: u8, g: u8, b: u8) -> Color {
+    Color::from_rgb(r as f32 / 255f32, g as f32 / 255f32, b as f32 /255f32)
+}
+// #[allow(dead_code)]
+pub fn get_rgba_color(r: u8, g: u8, b: u8, a: u8) -> Color {
+    Color::from_rgba(r as f32 / 255f32, g as f32 / 255f32, b as f32 /255f32, a as f32 / 255f32)
+}
+
+pub fn mix_color(from: Color, to: Color, t: f32) -> Color {
+    Color::from_rgba(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+pub fn equal_radius(radius: u32) -> Radius{
+    Radius{ top_left: radius as f32, top_right: radius as f32, bottom_right: radius as f32, bottom_left: radius as f32 }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ThemeChoice {
+    #[default]
+    System,
+    Builtin(Theme),
+    Custom(String),
+}
+
+impl ThemeChoice {
+    pub fn all(custom: &[CustomTheme]) -> Vec<ThemeChoice> {
+        std::iter::once(ThemeChoice::System)
+            .chain(Theme::ALL.iter().cloned().map(ThemeChoice::Builtin))
+            .chain(custom.iter().map(|theme| ThemeChoice::Custom(theme.name.clone())))
+            .collect()
+    }
+
+    // Inverse of `name`, unknown names fall back to following the system
+    pub fn from_name(name: &str) -> Self {
+        if let Some(custom) = name.strip_prefix("custom:") {
+            ThemeChoice::Custom(custom.to_owned())
+        } else if let Some(theme) = Theme::ALL.iter().find(|theme| theme.to_string() == name) {
+            ThemeChoice::Builtin(theme.clone())
+        } else {
+            ThemeChoice::System
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ThemeChoice::System => String::from("System"),
+            ThemeChoice::Builtin(theme) => theme.to_string(),
+            ThemeChoice::Custom(name) => format!("custom:{}", name),
+        }
+    }
+
+    pub fn custom<'a>(&self, custom: &'a [CustomTheme]) -> Option<&'a CustomTheme> {
+        match self {
+            ThemeChoice::Custom(name) => custom.iter().find(|theme| &theme.name == name),
+            _ => None,
+        }
+    }
+
+    pub fn resolve(&self, system: &Theme, custom: &[CustomTheme]) -> Theme {
+        match self {
+            ThemeChoice::System => system.clone(),
+            ThemeChoice::Builtin(theme) => theme.clone(),
+            // A custom theme whose file disappeared behaves like following the system
+            ThemeChoice::Custom(_) => self.custom(custom).map_or_else(|| system.clone(), |theme| theme.theme.clone()),
+        }
+    }
+
+    pub fn metrics(&self, custom: &[CustomTheme]) -> Metrics {
+        self.custom(custom).map(|theme| theme.metrics).unwrap_or_default()
+    }
+}
+
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeChoice::System => write!(f, "Follow System"),
+            ThemeChoice::Builtin(theme) => write!(f, "{}", theme),
+            ThemeChoice::Custom(name) => write!(f, "{} (custom)", name),
+        }
+    }
+}
+
+// Blocks on the desktop portal / registry, call it off the UI thread where possible
+pub fn system_theme() -> Theme {
+    match dark_light::detect() {
+        Ok(dark_light::Mode::Light) => Theme::Light,
+        _ => Theme::Dark,
+    }
+}
+
+pub mod buttons {
+    use iced::widget::button;
+    use iced::{Border, Color};
+    use super::*;
+
+    #[allow(dead_code)]
+    pub fn style_button(button_col: Color, text_col: Color, radius:i32) -> button::Style{
+        button::Style{
+            text_color: text_col,
+            border: Border::default().rounded(radius),
+            ..Default::default()
+        }.with_background(button_col)
+        
+    }
+
+    #[allow(dead_code)]
+    pub fn style_from_rgba_button(r: u8, g: u8, b: u8, a: u8, text_col: Color, radius:i32) -> button::Style{
+        button::Style{
+            text_color: text_col,
+            border: Border::default().rounded(radius),
+            ..Default::default()
+        }.with_background(get_rgba_color(r, g, b, a))
+    }
+
+    #[allow(dead_code)]
+    pub fn style_from_rgb_button(r: u8, g: u8, b: u8, text_col: Color, radius:i32) -> button::Style{
+        button::Style{
+            text_color: text_col,
+            border: Border::default().rounded(radius),
+            ..Default::default()
+        }.with_background(get_rgb_color(r, g, b))
+    }
+
+    pub fn themed(metrics: Metrics) -> impl Fn(&Theme, button::Status) -> button::Style {
+        move |theme, status| {
+            let style = button::primary(theme, status);
+            button::Style {
+                border: style.border.rounded(metrics.button_radius),
+                ..style
+            }
+        }
+    }
+}
+
+pub mod inputs {
+    use iced::widget::text_input;
+    use iced::Border;
+    use super::*;
+
+    pub fn themed(metrics: Metrics) -> impl Fn(&Theme, text_input::Status) -> text_input::Style {
+        move |theme, status| {
+            let style = text_input::default(theme, status);
+            text_input::Style {
+                border: Border {
+                    width: metrics.input_border,
+                    radius: metrics.input_radius.into(),
+                    ..style.border
+                },
+                ..style
+            }
+        }
+    }
+
+    pub fn error(metrics: Metrics) -> impl Fn(&Theme, text_input::Status) -> text_input::Style {
+        move |theme, status| {
+            let danger = theme.extended_palette().danger;
+            let color = metrics.error_color.unwrap_or(danger.base.color);
+            let style = themed(metrics)(theme, status);
+            text_input::Style {
+                border: Border {
+                    color,
+                    ..style.border
+                },
+                value: color,
+                selection: danger.weak.color,
+                ..style
+            }
+        }
+    }
+    // Switches to the error style while the input doesn't hold a valid value
+    pub fn validated(metrics: Metrics, valid: bool) -> impl Fn(&Theme, text_input::Status) -> text_input::Style {
+        move |theme, status| {
+            if valid {
+                themed(metrics)(theme, status)
+            } else {
+                error(metrics)(theme, status)
+            }
+        }
+    }
+}
+
+pub mod sliders {
+    use iced::widget::slider;
+    use super::*;
+
+    pub fn themed(metrics: Metrics) -> impl Fn(&Theme, slider::Status) -> slider::Style {
+        move |theme, status| {
+            let style = slider::default(theme, status);
+            slider::Style {
+                rail: slider::Rail {
+                    border: style.rail.border.rounded(metrics.slider_radius.min(style.rail.width / 2.0)),
+                    ..style.rail
+                },
+                ..style.with_circular_handle(metrics.slider_radius)
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use iced::{theme::Palette, Color, Font, Theme};
+use serde::Deserialize;
+
+use crate::config;
+
+// A theme file is a TOML document, every table and key is optional:
+//
+//     name = "Midnight"
+//     base = "dark"              # built-in palette the missing colors come from ("dark" or "light")
+//
+//     [palette]
+//     background = "#101018"
+//     text = "#e0e0f0"
+//     primary = "#6464ff"
+//     success = "#40c060"
+//     danger = "#ff4040"
+//
+//     [radius]
+//     container = 15
+//     button = 4
+//     input = 4
+//     slider = 7
+//
+//     [border]
+//     container = 3
+//     input = 1
+//
+//     [font]
+//     family = "Fira Sans"       # family and size are picked up on the next start
+//     size = 16
+//
+//     [error]
+//     color = "#ff6060"
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeDef {
+    pub name: Option<String>,
+    pub base: Option<String>,
+    pub palette: PaletteDef,
+    pub radius: RadiusDef,
+    pub border: BorderDef,
+    pub font: FontDef,
+    pub error: ErrorDef,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteDef {
+    pub background: Option<String>,
+    pub text: Option<String>,
+    pub primary: Option<String>,
+    pub success: Option<String>,
+    pub danger: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RadiusDef {
+    pub container: Option<u32>,
+    pub button: Option<f32>,
+    pub input: Option<f32>,
+    pub slider: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BorderDef {
+    pub container: Option<i32>,
+    pub input: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FontDef {
+    pub family: Option<String>,
+    pub size: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ErrorDef {
+    pub color: Option<String>,
+}
+
+// Everything a theme can tune beyond the palette, with the values the app used before theme files existed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub container_radius: u32,
+    pub container_border: i32,
+    pub button_radius: f32,
+    pub input_radius: f32,
+    pub input_border: f32,
+    pub slider_radius: f32,
+    pub error_color: Option<Color>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            container_radius: 15,
+            container_border: 3,
+            button_radius: 2.0,
+            input_radius: 2.0,
+            input_border: 1.0,
+            slider_radius: 7.0,
+            error_color: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CustomTheme {
+    pub name: String,
+    pub path: PathBuf,
+    pub theme: Theme,
+    pub metrics: Metrics,
+    pub font_family: Option<String>,
+    pub text_size: Option<f32>,
+}
+
+impl CustomTheme {
+    pub fn from_def(def: ThemeDef, path: &Path) -> Result<Self, ThemeError> {
+        let name = def.name.unwrap_or_else(|| {
+            path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default()
+        });
+        let base = match def.base.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("dark") => Palette::DARK,
+            Some("light") => Palette::LIGHT,
+            Some(other) => return Err(ThemeError::Value(format!("unknown base palette \"{}\"", other))),
+        };
+        let palette = Palette {
+            background: color_or(def.palette.background, base.background)?,
+            text: color_or(def.palette.text, base.text)?,
+            primary: color_or(def.palette.primary, base.primary)?,
+            success: color_or(def.palette.success, base.success)?,
+            danger: color_or(def.palette.danger, base.danger)?,
+        };
+        let defaults = Metrics::default();
+        let metrics = Metrics {
+            container_radius: def.radius.container.unwrap_or(defaults.container_radius),
+            container_border: def.border.container.unwrap_or(defaults.container_border),
+            button_radius: def.radius.button.unwrap_or(defaults.button_radius),
+            input_radius: def.radius.input.unwrap_or(defaults.input_radius),
+            input_border: def.border.input.unwrap_or(defaults.input_border),
+            slider_radius: def.radius.slider.unwrap_or(defaults.slider_radius),
+            error_color: def.error.color.map(|color| parse_color(&color)).transpose()?,
+        };
+        Ok(Self {
+            theme: Theme::custom(name.clone(), palette),
+            name,
+            path: path.to_path_buf(),
+            metrics,
+            font_family: def.font.family,
+            text_size: def.font.size,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ThemeError> {
+        let contents = fs::read_to_string(path).map_err(ThemeError::Io)?;
+        let def: ThemeDef = toml::from_str(&contents).map_err(|error| ThemeError::Parse(error.to_string()))?;
+        Self::from_def(def, path)
+    }
+
+    // iced keeps font names for the lifetime of the program, so this is only meant to be called once at startup
+    pub fn font(&self) -> Option<Font> {
+        self.font_family.as_ref().map(|family| Font::with_name(Box::leak(family.clone().into_boxed_str())))
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Parse(String),
+    Value(String),
+}
+
+impl Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(error) => write!(f, "{}", error),
+            ThemeError::Parse(error) => write!(f, "{}", error),
+            ThemeError::Value(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+pub fn themes_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join("themes"))
+}
+
+// Modification stamps of every theme file, compared between polls to detect edits
+pub fn scan() -> Vec<(PathBuf, Option<SystemTime>)> {
+    let Some(entries) = themes_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut stamps: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            (path, modified)
+        })
+        .collect();
+    stamps.sort();
+    stamps
+}
+
+pub fn load_themes() -> Vec<CustomTheme> {
+    scan()
+        .into_iter()
+        .filter_map(|(path, _)| {
+            CustomTheme::load(&path)
+                .map_err(|error| eprintln!("Skipping theme {}: {}", path.display(), error))
+                .ok()
+        })
+        .collect()
+}
+
+fn parse_color(color: &str) -> Result<Color, ThemeError> {
+    Color::parse(color).ok_or_else(|| ThemeError::Value(format!("invalid color \"{}\"", color)))
+}
+
+fn color_or(color: Option<String>, default: Color) -> Result<Color, ThemeError> {
+    color.map_or(Ok(default), |color| parse_color(&color))
+}
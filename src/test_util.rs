@@ -0,0 +1,18 @@
+// Helpers shared by the unit tests
+use std::{env, path::PathBuf};
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+// A day in March 2026, the 16th is a Monday
+pub fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+}
+
+pub fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    date(day).and_hms_opt(hour, minute, 0).unwrap()
+}
+
+// Named after the test process, so parallel runs don't share files
+pub fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("vol-limiter-{}-{}", std::process::id(), name))
+}
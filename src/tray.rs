@@ -0,0 +1,45 @@
+use ksni::{menu::StandardItem, MenuItem, Tray, TrayMethods};
+
+use crate::ipc::{self, Request};
+
+// Status area icon on desktops with StatusNotifierItem support, its entries go through the same requests as the CLI
+#[derive(Debug)]
+struct VolumeTray;
+
+impl Tray for VolumeTray {
+    fn id(&self) -> String {
+        String::from("vol-limiter")
+    }
+
+    fn title(&self) -> String {
+        String::from("Volume Limiter")
+    }
+
+    fn icon_name(&self) -> String {
+        String::from("audio-volume-medium")
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        vec![
+            // The tray can't ask for the PIN, so the app refuses this while the settings are locked
+            StandardItem {
+                label: String::from("Cancel sleep timer"),
+                activate: Box::new(|_| send(Request::CancelSleep)),
+                ..Default::default()
+            }.into(),
+        ]
+    }
+}
+
+fn send(request: Request) {
+    if let Err(error) = ipc::send(request, None) {
+        eprintln!("Failed to send \"{}\" from the tray: {}", request, error);
+    }
+}
+
+// Keeps running after this returns, a desktop without a tray only gets a message on stderr
+pub async fn spawn() {
+    if let Err(error) = VolumeTray.spawn().await {
+        eprintln!("Failed to show the tray icon: {}", error);
+    }
+}
@@ -0,0 +1,168 @@
+use std::{io, process::Command};
+
+use crate::volume::Volume;
+
+// What cpvc doesn't cover (capture devices, channels, levels past 100%) goes through the platform's mixer CLI,
+// pactl on Linux (PulseAudio and PipeWire) and osascript on macOS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Output,
+    Input,
+}
+
+impl Direction {
+    fn pactl_kind(self) -> &'static str {
+        match self {
+            Direction::Output => "sink",
+            Direction::Input => "source",
+        }
+    }
+
+    fn pactl_default(self) -> &'static str {
+        match self {
+            Direction::Output => "@DEFAULT_SINK@",
+            Direction::Input => "@DEFAULT_SOURCE@",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub name: String,
+    pub volume: Volume,
+}
+
+fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "not supported on this platform")
+}
+
+fn run(program: &str, args: &[&str]) -> io::Result<String> {
+    let output = Command::new(program).args(args).output()?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    } else {
+        Err(io::Error::other(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim())))
+    }
+}
+
+// Parses `pactl get-sink-volume` output, e.g. "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: ..."
+pub fn parse_pactl_volume(output: &str) -> Vec<Channel> {
+    let Some(line) = output.lines().find_map(|line| line.trim().strip_prefix("Volume:")) else {
+        return Vec::new();
+    };
+    line.split(',')
+        .filter_map(|channel| {
+            let (name, levels) = channel.split_once(':')?;
+            let percent = levels.split('/').nth(1)?.trim().trim_end_matches('%').parse().ok()?;
+            Some(Channel { name: name.trim().to_owned(), volume: Volume::from_percent(percent)? })
+        })
+        .collect()
+}
+
+pub fn devices(direction: Direction) -> io::Result<Vec<String>> {
+    if cfg!(target_os = "linux") {
+        let output = run("pactl", &["list", "short", &format!("{}s", direction.pactl_kind())])?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split('\t').nth(1))
+            // Monitors mirror an output, they aren't microphones
+            .filter(|name| !name.ends_with(".monitor"))
+            .map(str::to_owned)
+            .collect())
+    } else {
+        Err(unsupported())
+    }
+}
+
+pub fn default_device(direction: Direction) -> io::Result<String> {
+    if cfg!(target_os = "linux") {
+        run("pactl", &[&format!("get-default-{}", direction.pactl_kind())])
+    } else {
+        Err(unsupported())
+    }
+}
+
+pub fn channels(direction: Direction) -> io::Result<Vec<Channel>> {
+    if cfg!(target_os = "linux") {
+        let output = run("pactl", &[&format!("get-{}-volume", direction.pactl_kind()), direction.pactl_default()])?;
+        Ok(parse_pactl_volume(&output))
+    } else if cfg!(target_os = "macos") && direction == Direction::Input {
+        let output = run("osascript", &["-e", "input volume of (get volume settings)"])?;
+        let percent = output.parse().map_err(io::Error::other)?;
+        let volume = Volume::from_percent(percent).ok_or_else(|| io::Error::other(format!("volume out of range: {}%", percent)))?;
+        Ok(vec![Channel { name: String::from("mono"), volume }])
+    } else {
+        Err(unsupported())
+    }
+}
+
+// Average over the channels, like cpvc reports the output volume
+pub fn volume(direction: Direction) -> io::Result<Volume> {
+    let channels = channels(direction)?;
+    if channels.is_empty() {
+        return Err(io::Error::other("no channels reported"));
+    }
+    let total: f32 = channels.iter().map(|channel| channel.volume.linear()).sum();
+    Volume::from_linear(total / channels.len() as f32).ok_or_else(|| io::Error::other("invalid channel levels"))
+}
+
+pub fn set_volume(direction: Direction, volume: Volume) -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        run("pactl", &[&format!("set-{}-volume", direction.pactl_kind()), direction.pactl_default(), &format!("{}%", volume.percent())]).map(|_| ())
+    } else if cfg!(target_os = "macos") && direction == Direction::Input {
+        run("osascript", &["-e", &format!("set volume input volume {}", volume.percent().min(100))]).map(|_| ())
+    } else {
+        Err(unsupported())
+    }
+}
+
+// One level per channel, in the order `channels` reports them
+pub fn set_channels(direction: Direction, volumes: &[Volume]) -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        let levels: Vec<String> = volumes.iter().map(|volume| format!("{}%", volume.percent())).collect();
+        let mut args = vec![format!("set-{}-volume", direction.pactl_kind()), direction.pactl_default().to_owned()];
+        args.extend(levels);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run("pactl", &args).map(|_| ())
+    } else {
+        Err(unsupported())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(channels: &[Channel]) -> Vec<(&str, u16)> {
+        channels.iter().map(|channel| (channel.name.as_str(), channel.volume.percent())).collect()
+    }
+
+    #[test]
+    fn parses_stereo_output() {
+        let output = "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: 39322 /  60% / -13.31 dB\n        balance 0.17\n";
+        assert_eq!(levels(&parse_pactl_volume(output)), [("front-left", 50), ("front-right", 60)]);
+    }
+
+    #[test]
+    fn parses_surround_and_amplified_output() {
+        let output = "Volume: front-left: 98304 / 150% / 10.57 dB,   front-right: 65536 / 100% / 0.00 dB,   rear-left: 65536 / 100% / 0.00 dB,   \
+            rear-right: 65536 / 100% / 0.00 dB,   front-center: 0 /   0% / -inf dB,   lfe: 65536 / 100% / 0.00 dB\n        balance -0.33\n";
+        assert_eq!(
+            levels(&parse_pactl_volume(output)),
+            [("front-left", 150), ("front-right", 100), ("rear-left", 100), ("rear-right", 100), ("front-center", 0), ("lfe", 100)],
+        );
+    }
+
+    #[test]
+    fn parses_mono_sources() {
+        assert_eq!(levels(&parse_pactl_volume("Volume: mono: 45875 /  70% / -9.29 dB\n")), [("mono", 70)]);
+    }
+
+    #[test]
+    fn unexpected_output_has_no_channels() {
+        assert!(parse_pactl_volume("").is_empty());
+        assert!(parse_pactl_volume("Failed to get sink information: No such entity\n").is_empty());
+        let garbled = "Volume: front-left: 32768 / loud / -18.06 dB,   front-right: 39322 /  60% / -13.31 dB";
+        assert_eq!(levels(&parse_pactl_volume(garbled)), [("front-right", 60)]);
+    }
+}
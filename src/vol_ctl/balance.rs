@@ -0,0 +1,117 @@
+use crate::{vol_ctl::backend::Channel, volume::Volume};
+
+// Balance runs from -100 (left only) through 0 (centered) to 100 (right only)
+pub const RANGE: std::ops::RangeInclusive<i16> = -100..=100;
+
+fn is_left(channel: &Channel) -> bool {
+    channel.name.contains("left")
+}
+
+fn is_right(channel: &Channel) -> bool {
+    channel.name.contains("right")
+}
+
+fn loudest(channels: &[Channel], side: fn(&Channel) -> bool) -> Option<Volume> {
+    channels.iter().filter(|channel| side(channel)).map(|channel| channel.volume).max()
+}
+
+// None for layouts without both a left and a right channel
+pub fn balance_of(channels: &[Channel]) -> Option<i16> {
+    let left = loudest(channels, is_left)?.linear();
+    let right = loudest(channels, is_right)?.linear();
+    if left == right {
+        Some(0)
+    } else if left > right {
+        Some(-((1.0 - right / left) * 100.0).round() as i16)
+    } else {
+        Some(((1.0 - left / right) * 100.0).round() as i16)
+    }
+}
+
+// Levels for every channel at `volume` and `balance`, the louder side stays at `volume` so it never goes past it
+pub fn apply_balance(channels: &[Channel], volume: Volume, balance: i16) -> Vec<Volume> {
+    let balance = balance.clamp(*RANGE.start(), *RANGE.end()) as f32 / 100.0;
+    let left = Volume::from_linear(volume.linear() * (1.0 - balance).min(1.0)).unwrap_or(Volume::MUTE);
+    let right = Volume::from_linear(volume.linear() * (1.0 + balance).min(1.0)).unwrap_or(Volume::MUTE);
+    channels
+        .iter()
+        .map(|channel| {
+            if is_left(channel) {
+                left
+            } else if is_right(channel) {
+                right
+            } else {
+                volume
+            }
+        })
+        .collect()
+}
+
+// Every channel held under the ceiling on its own, None when none of them is over
+pub fn cap_channels(channels: &[Channel], ceiling: Volume) -> Option<Vec<Volume>> {
+    channels
+        .iter()
+        .any(|channel| channel.volume.percent() > ceiling.percent())
+        .then(|| channels.iter().map(|channel| channel.volume.min(ceiling)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(levels: &[(&str, u16)]) -> Vec<Channel> {
+        levels.iter().map(|&(name, percent)| Channel { name: name.to_owned(), volume: Volume::from_percent(percent).unwrap() }).collect()
+    }
+
+    fn percents(volumes: &[Volume]) -> Vec<u16> {
+        volumes.iter().map(|volume| volume.percent()).collect()
+    }
+
+    const SURROUND: [&str; 6] = ["front-left", "front-right", "rear-left", "rear-right", "front-center", "lfe"];
+
+    #[test]
+    fn balance_leans_toward_the_louder_side() {
+        assert_eq!(balance_of(&channels(&[("front-left", 60), ("front-right", 60)])), Some(0));
+        assert_eq!(balance_of(&channels(&[("front-left", 80), ("front-right", 40)])), Some(-50));
+        assert_eq!(balance_of(&channels(&[("front-left", 40), ("front-right", 80)])), Some(50));
+        assert_eq!(balance_of(&channels(&[("front-left", 0), ("front-right", 80)])), Some(100));
+    }
+
+    #[test]
+    fn layouts_without_both_sides_have_no_balance() {
+        assert_eq!(balance_of(&channels(&[("mono", 60)])), None);
+        assert_eq!(balance_of(&channels(&[("front-left", 60), ("front-center", 60)])), None);
+        assert_eq!(balance_of(&[]), None);
+    }
+
+    #[test]
+    fn surround_layouts_compare_the_loudest_channel_of_each_side() {
+        let surround = channels(&SURROUND.into_iter().zip([80, 40, 20, 40, 100, 100]).collect::<Vec<_>>());
+        assert_eq!(balance_of(&surround), Some(-50));
+    }
+
+    #[test]
+    fn applying_a_balance_turns_down_the_other_side_only() {
+        let stereo = channels(&[("front-left", 50), ("front-right", 50)]);
+        assert_eq!(percents(&apply_balance(&stereo, Volume::from(80), 0)), [80, 80]);
+        assert_eq!(percents(&apply_balance(&stereo, Volume::from(80), -50)), [80, 40]);
+        assert_eq!(percents(&apply_balance(&stereo, Volume::from(80), 25)), [60, 80]);
+        // Past the range is as far as it goes
+        assert_eq!(percents(&apply_balance(&stereo, Volume::from(80), 300)), [0, 80]);
+    }
+
+    #[test]
+    fn applying_a_balance_leaves_centered_channels_at_the_volume() {
+        let surround = channels(&SURROUND.map(|name| (name, 50)));
+        assert_eq!(percents(&apply_balance(&surround, Volume::from(60), 50)), [30, 60, 30, 60, 60, 60]);
+        assert_eq!(balance_of(&channels(&SURROUND.into_iter().zip([30, 60, 30, 60, 60, 60]).collect::<Vec<_>>())), Some(50));
+    }
+
+    #[test]
+    fn only_channels_over_the_ceiling_are_capped() {
+        let levels = channels(&[("front-left", 50), ("front-right", 90), ("front-center", 70)]);
+        assert_eq!(cap_channels(&levels, Volume::from(70)).as_deref().map(percents), Some(vec![50, 70, 70]));
+        assert_eq!(cap_channels(&levels, Volume::from(90)), None);
+        assert_eq!(cap_channels(&[], Volume::from(50)), None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::volume::Volume;
+
+// Span of the dB curve, the bottom step of the slider sits this far below full volume
+const DB_RANGE: f32 = 40.0;
+const POSITIONS: f32 = 100.0;
+
+// How slider positions (0 to 100) map onto backend volume, the curved ones give the quiet end more room
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Curve {
+    #[default]
+    Linear,
+    Cubic,
+    Decibel,
+}
+
+impl Curve {
+    pub const ALL: [Curve; 3] = [Curve::Linear, Curve::Cubic, Curve::Decibel];
+
+    pub fn to_volume(self, position: u8) -> u8 {
+        let position = position.min(POSITIONS as u8) as f32 / POSITIONS;
+        let volume = match self {
+            Curve::Linear => Volume::from_linear(position),
+            Curve::Cubic => Volume::from_linear(position.powi(3)),
+            Curve::Decibel if position == 0.0 => Some(Volume::MUTE),
+            Curve::Decibel => Volume::from_db((position - 1.0) * DB_RANGE),
+        };
+        volume.unwrap_or(Volume::MUTE).percent_u8()
+    }
+
+    pub fn to_position(self, volume: u8) -> u8 {
+        let volume = Volume::from(volume.min(100));
+        let position = match self {
+            Curve::Linear => volume.linear(),
+            Curve::Cubic => volume.linear().cbrt(),
+            Curve::Decibel if volume.is_mute() => 0.0,
+            Curve::Decibel => (volume.db() / DB_RANGE + 1.0).max(0.0),
+        };
+        (position * POSITIONS).round() as u8
+    }
+
+    // Volume one slider position up or down, always moving by at least a percent
+    pub fn step(self, volume: u8, up: bool) -> u8 {
+        let position = self.to_position(volume);
+        if up {
+            self.to_volume(position.saturating_add(1)).max(volume.saturating_add(1))
+        } else {
+            self.to_volume(position.saturating_sub(1)).min(volume.saturating_sub(1))
+        }
+    }
+}
+
+impl Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Curve::Linear => write!(f, "Linear"),
+            Curve::Cubic => write!(f, "Cubic"),
+            Curve::Decibel => write!(f, "Decibel"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ends_map_to_mute_and_full() {
+        for curve in Curve::ALL {
+            assert_eq!(curve.to_volume(0), 0, "{}", curve);
+            assert_eq!(curve.to_volume(100), 100, "{}", curve);
+            assert_eq!(curve.to_position(0), 0, "{}", curve);
+            assert_eq!(curve.to_position(100), 100, "{}", curve);
+        }
+    }
+
+    #[test]
+    fn positions_past_the_end_are_capped() {
+        assert_eq!(Curve::Linear.to_volume(150), 100);
+        assert_eq!(Curve::Cubic.to_position(150), 100);
+    }
+
+    #[test]
+    fn curved_mappings_give_the_quiet_end_more_room() {
+        assert_eq!(Curve::Linear.to_volume(50), 50);
+        assert_eq!(Curve::Cubic.to_volume(50), 13);
+        // Halfway down a 40 dB range is -20 dB
+        assert_eq!(Curve::Decibel.to_volume(50), 10);
+        assert_eq!(Curve::Decibel.to_position(10), 50);
+    }
+
+    #[test]
+    fn positions_round_trip_through_volumes() {
+        for curve in Curve::ALL {
+            for volume in [0, 10, 25, 50, 75, 100] {
+                let back = curve.to_volume(curve.to_position(volume));
+                assert!(back.abs_diff(volume) <= 1, "{}: {}% came back as {}%", curve, volume, back);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_always_move_by_at_least_a_percent() {
+        for curve in Curve::ALL {
+            for volume in 1..100 {
+                assert!(curve.step(volume, true) > volume, "{} up from {}%", curve, volume);
+                assert!(curve.step(volume, false) < volume, "{} down from {}%", curve, volume);
+            }
+            assert_eq!(curve.step(0, false), 0);
+        }
+    }
+}
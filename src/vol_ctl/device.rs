@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+const HEADPHONE_HINTS: [&str; 8] = ["headphone", "headset", "earphone", "earbud", "airpods", "buds", "in-ear", "hands-free"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceClass {
+    Headphones,
+    Speakers,
+}
+
+// Device names are all we get from the backend, so the class is a best guess from the name
+pub fn classify(name: &str) -> DeviceClass {
+    let name = name.to_lowercase();
+    if HEADPHONE_HINTS.iter().any(|hint| name.contains(hint)) {
+        DeviceClass::Headphones
+    } else {
+        DeviceClass::Speakers
+    }
+}
+
+// Loudness a device reaches at 100% output, used to turn a volume percentage into an estimated dB SPL
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SensitivityProfile {
+    pub max_spl_db: f32,
+}
+
+impl SensitivityProfile {
+    pub fn for_class(class: DeviceClass) -> Self {
+        match class {
+            DeviceClass::Headphones => Self { max_spl_db: 100.0 },
+            DeviceClass::Speakers => Self { max_spl_db: 85.0 },
+        }
+    }
+
+    pub fn for_device(name: &str) -> Self {
+        Self::for_class(classify(name))
+    }
+
+    // System volume sliders follow a cubic amplitude curve, so every halving of the percentage is roughly -18 dB
+    pub fn spl_at(&self, percent: u8) -> Option<f32> {
+        if percent == 0 {
+            None
+        } else {
+            Some(self.max_spl_db + 60.0 * (percent as f32 / 100.0).log10())
+        }
+    }
+
+    pub fn percent_for(&self, spl_db: f32) -> u8 {
+        let percent = 100.0 * 10f32.powf((spl_db - self.max_spl_db) / 60.0);
+        percent.round().clamp(0.0, 100.0) as u8
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, vol_ctl::device::SensitivityProfile};
+
+const DOSE_FILE: &str = "dose.toml";
+const HISTORY_DAYS: usize = 7;
+// Share of the daily allowance after which the ceiling starts closing in, when tightening is enabled
+const TIGHTEN_FROM: f32 = 0.75;
+// Past the full allowance the ceiling keeps sinking until this share, where it settles 10 dB under the reference
+const SETTLE_AT: f32 = 1.25;
+const SETTLED_BELOW_DB: f32 = 10.0;
+
+// Both standards use the equal-energy rule: every 3 dB above the reference level halves the allowed time
+pub const EXCHANGE_RATE_DB: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DoseStandard {
+    // 80 dB for 40 hours a week (WHO/ITU safe listening)
+    #[default]
+    Who,
+    // 85 dB for 8 hours a day (NIOSH occupational limit)
+    Niosh,
+}
+
+impl DoseStandard {
+    pub const ALL: [DoseStandard; 2] = [DoseStandard::Who, DoseStandard::Niosh];
+
+    pub fn reference_db(self) -> f32 {
+        match self {
+            DoseStandard::Who => 80.0,
+            DoseStandard::Niosh => 85.0,
+        }
+    }
+
+    pub fn reference_duration(self) -> Duration {
+        match self {
+            DoseStandard::Who => Duration::from_secs(40 * 3600 / 7),
+            DoseStandard::Niosh => Duration::from_secs(8 * 3600),
+        }
+    }
+
+    pub fn allowed_time(self, spl_db: f32) -> Duration {
+        let factor = 2f32.powf((self.reference_db() - spl_db) / EXCHANGE_RATE_DB);
+        self.reference_duration().mul_f32(factor)
+    }
+}
+
+impl Display for DoseStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoseStandard::Who => write!(f, "WHO (80 dB, 40 h/week)"),
+            DoseStandard::Niosh => write!(f, "NIOSH (85 dB, 8 h/day)"),
+        }
+    }
+}
+
+// Dose is a fraction of the daily allowance, 1.0 being the full budget
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DayDose {
+    pub date: NaiveDate,
+    pub dose: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dosimeter {
+    pub standard: DoseStandard,
+    today: DayDose,
+    history: VecDeque<DayDose>,
+}
+
+impl Dosimeter {
+    pub fn new(standard: DoseStandard, today: NaiveDate) -> Self {
+        Self {
+            standard,
+            today: DayDose { date: today, dose: 0.0 },
+            history: VecDeque::with_capacity(HISTORY_DAYS),
+        }
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        config::state_dir().map(|dir| dir.join(DOSE_FILE))
+    }
+
+    pub fn load(standard: DoseStandard, today: NaiveDate) -> Self {
+        let loaded = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str::<Self>(&contents).ok());
+        let mut dosimeter = loaded.unwrap_or_else(|| Self::new(standard, today));
+        dosimeter.standard = standard;
+        dosimeter.roll_over(today);
+        dosimeter
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self).map_err(io::Error::other)?)
+    }
+
+    // Adds the exposure of one sample, `spl_db` is None while muted
+    pub fn record(&mut self, date: NaiveDate, spl_db: Option<f32>, elapsed: Duration) {
+        self.roll_over(date);
+        if let Some(spl_db) = spl_db {
+            self.today.dose += elapsed.as_secs_f32() / self.standard.allowed_time(spl_db).as_secs_f32();
+        }
+    }
+
+    pub fn daily_percent(&self) -> f32 {
+        self.today.dose * 100.0
+    }
+
+    // The last seven days, oldest first and ending with today, days without listening are reported as zero
+    pub fn week(&self) -> Vec<DayDose> {
+        (0..HISTORY_DAYS as u64).rev()
+            .filter_map(|days_ago| self.today.date.checked_sub_days(chrono::Days::new(days_ago)))
+            .map(|date| {
+                if date == self.today.date {
+                    self.today
+                } else {
+                    self.history.iter().find(|day| day.date == date).copied().unwrap_or(DayDose { date, dose: 0.0 })
+                }
+            })
+            .collect()
+    }
+
+    // Lowest ceiling that keeps the rest of the day within budget, None while plenty is left
+    pub fn budget_ceiling(&self, profile: &SensitivityProfile) -> Option<u8> {
+        let dose = self.today.dose;
+        if dose < TIGHTEN_FROM {
+            return None;
+        }
+        // Falls steadily from the device maximum to the reference level as the budget runs out, then on to
+        // 10 dB under it, where the allowance lasts ten times longer, so there is never a sudden drop
+        let reference = self.standard.reference_db().min(profile.max_spl_db);
+        let target = if dose < 1.0 {
+            let progress = (dose - TIGHTEN_FROM) / (1.0 - TIGHTEN_FROM);
+            profile.max_spl_db + (reference - profile.max_spl_db) * progress
+        } else {
+            let progress = ((dose - 1.0) / (SETTLE_AT - 1.0)).min(1.0);
+            reference - SETTLED_BELOW_DB * progress
+        };
+        Some(profile.percent_for(target))
+    }
+
+    fn roll_over(&mut self, date: NaiveDate) {
+        if date != self.today.date {
+            self.history.push_back(self.today);
+            while self.history.len() >= HISTORY_DAYS {
+                self.history.pop_front();
+            }
+            self.today = DayDose { date, dose: 0.0 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::date as day;
+
+    use super::*;
+
+    const HEADPHONES: SensitivityProfile = SensitivityProfile { max_spl_db: 100.0 };
+
+    // NIOSH allows 8 hours at its 85 dB reference, so the dose is the share of those hours
+    fn with_dose(dose: f32) -> Dosimeter {
+        let mut dosimeter = Dosimeter::new(DoseStandard::Niosh, day(16));
+        dosimeter.record(day(16), Some(85.0), Duration::from_secs(8 * 3600).mul_f32(dose));
+        dosimeter
+    }
+
+    #[test]
+    fn allowed_time_halves_every_three_db() {
+        let standard = DoseStandard::Niosh;
+        assert_eq!(standard.allowed_time(85.0), Duration::from_secs(8 * 3600));
+        assert_eq!(standard.allowed_time(88.0), Duration::from_secs(4 * 3600));
+        assert_eq!(standard.allowed_time(82.0), Duration::from_secs(16 * 3600));
+    }
+
+    #[test]
+    fn muted_samples_add_nothing() {
+        let mut dosimeter = with_dose(0.5);
+        dosimeter.record(day(16), None, Duration::from_secs(3600));
+        assert_eq!(dosimeter.daily_percent(), 50.0);
+    }
+
+    #[test]
+    fn a_new_day_starts_from_zero_and_keeps_the_last_one() {
+        let mut dosimeter = with_dose(0.5);
+        dosimeter.record(day(18), Some(85.0), Duration::from_secs(2 * 3600));
+        assert_eq!(dosimeter.daily_percent(), 25.0);
+        let week: Vec<f32> = dosimeter.week().iter().map(|day| day.dose).collect();
+        assert_eq!(week, [0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.25]);
+        assert_eq!(dosimeter.week().last().map(|day| day.date), Some(day(18)));
+    }
+
+    #[test]
+    fn budget_ceiling_waits_until_three_quarters() {
+        assert_eq!(with_dose(0.7).budget_ceiling(&HEADPHONES), None);
+        assert_eq!(with_dose(0.75).budget_ceiling(&HEADPHONES), Some(100));
+    }
+
+    #[test]
+    fn budget_ceiling_reaches_the_reference_then_settles_below_it() {
+        assert_eq!(with_dose(1.0).budget_ceiling(&HEADPHONES), Some(HEADPHONES.percent_for(85.0)));
+        assert_eq!(with_dose(1.25).budget_ceiling(&HEADPHONES), Some(HEADPHONES.percent_for(75.0)));
+        assert_eq!(with_dose(3.0).budget_ceiling(&HEADPHONES), Some(HEADPHONES.percent_for(75.0)));
+    }
+
+    #[test]
+    fn budget_ceiling_never_drops_suddenly() {
+        let ceilings: Vec<u8> = (75..=150).map(|hundredths| with_dose(hundredths as f32 / 100.0).budget_ceiling(&HEADPHONES).unwrap()).collect();
+        for pair in ceilings.windows(2) {
+            assert!(pair[1] <= pair[0], "ceiling rose: {:?}", pair);
+            assert!(pair[0] - pair[1] <= 3, "ceiling dropped from {}% to {}%", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn quiet_devices_settle_below_their_own_maximum() {
+        let speakers = SensitivityProfile { max_spl_db: 70.0 };
+        assert_eq!(with_dose(1.0).budget_ceiling(&speakers), Some(100));
+        assert_eq!(with_dose(1.25).budget_ceiling(&speakers), Some(speakers.percent_for(60.0)));
+    }
+}
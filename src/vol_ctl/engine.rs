@@ -0,0 +1,496 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use cpvc::{get_system_volume, set_system_volume};
+
+use crate::{vol_ctl::{backend::{self, Direction}, balance, slew::Slew}, volume::Volume};
+
+const TICK: Duration = Duration::from_millis(100);
+// Volume left above a falling ceiling fades down by this much every tick
+const FADE_STEP: u8 = 1;
+const OFF: u8 = u8::MAX;
+// Past any level a backend reports, so it never matches a reading
+const NO_WRITE: u16 = u16::MAX;
+// Reading every channel means running the mixer CLI, so it happens every few ticks only
+const CHANNEL_CHECK_TICKS: u32 = 5;
+
+// Everything the engine enforces, shared with the GUI so changes apply without restarting it
+#[derive(Debug)]
+pub struct Limits {
+    ceiling: AtomicU8,
+    floor: AtomicU8,
+    lock: AtomicU8,
+    slew_rate: AtomicU8,
+    allow_mute: AtomicBool,
+    max_volume: AtomicU8,
+    // Loudest amplification refused since the GUI last asked, 0 for none
+    over_amplified: AtomicU8,
+    // Readings pushed past the ceiling from outside since the GUI last asked
+    attempts: AtomicU32,
+    // Level the app itself last set, reading it back isn't somebody pushing the volume
+    own_write: AtomicU16,
+    // Set when the device changes, the level the OS restores for the new one is taken as a first reading
+    switched: AtomicBool,
+    // Level the engine itself last wrote, until a different reading comes in
+    written: AtomicU16,
+    // Volume before the first correction and after the latest one since the GUI last asked
+    clamp: Mutex<Option<(u8, u8)>>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            ceiling: AtomicU8::new(OFF),
+            floor: AtomicU8::new(OFF),
+            lock: AtomicU8::new(OFF),
+            slew_rate: AtomicU8::new(0),
+            allow_mute: AtomicBool::new(true),
+            max_volume: AtomicU8::new(OFF),
+            over_amplified: AtomicU8::new(0),
+            attempts: AtomicU32::new(0),
+            own_write: AtomicU16::new(NO_WRITE),
+            switched: AtomicBool::new(false),
+            written: AtomicU16::new(NO_WRITE),
+            clamp: Mutex::new(None),
+        }
+    }
+}
+
+impl Limits {
+    pub fn ceiling(&self) -> Option<u8> {
+        optional(self.ceiling.load(Ordering::Relaxed))
+    }
+
+    pub fn set_ceiling(&self, ceiling: Option<u8>) {
+        self.ceiling.store(ceiling.map_or(OFF, |ceiling| ceiling.min(100)), Ordering::Relaxed);
+    }
+
+    // Highest level software amplification may reach, backends like PulseAudio go past 100%
+    pub fn max_volume(&self) -> Option<u8> {
+        optional(self.max_volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_max_volume(&self, max_volume: Option<u8>) {
+        self.max_volume.store(max_volume.map_or(OFF, |max_volume| max_volume.max(100)), Ordering::Relaxed);
+    }
+
+    pub fn take_over_amplified(&self) -> Option<u8> {
+        optional_level(self.over_amplified.swap(0, Ordering::Relaxed))
+    }
+
+    pub fn take_attempts(&self) -> u32 {
+        self.attempts.swap(0, Ordering::Relaxed)
+    }
+
+    // Called before the app sets the volume, like Slew::set for the engine's own writes
+    pub fn expect_write(&self, volume: u8) {
+        self.own_write.store(volume as u16, Ordering::Relaxed);
+    }
+
+    // Anything counted since the last poll may have been the OS restoring the new device's level, so it's dropped too
+    pub fn device_switched(&self) {
+        self.attempts.store(0, Ordering::Relaxed);
+        self.switched.store(true, Ordering::Relaxed);
+    }
+
+    fn record_clamp(&self, before: u8, after: u8) {
+        self.written.store(after as u16, Ordering::Relaxed);
+        let mut clamp = self.clamp.lock().unwrap();
+        *clamp = Some((clamp.map_or(before, |(first, _)| first), after));
+    }
+
+    // Whether the volume is one the engine clamped or faded to, rather than anybody's choice
+    pub fn engine_wrote(&self, volume: u8) -> bool {
+        self.written.load(Ordering::Relaxed) == volume as u16
+    }
+
+    pub fn take_clamp(&self) -> Option<(u8, u8)> {
+        self.clamp.lock().unwrap().take()
+    }
+
+    pub fn floor(&self) -> Option<u8> {
+        optional(self.floor.load(Ordering::Relaxed))
+    }
+
+    pub fn set_floor(&self, floor: Option<u8>) {
+        self.floor.store(floor.map_or(OFF, |floor| floor.min(100)), Ordering::Relaxed);
+    }
+
+    pub fn lock(&self) -> Option<u8> {
+        optional(self.lock.load(Ordering::Relaxed))
+    }
+
+    pub fn set_lock(&self, lock: Option<u8>) {
+        self.lock.store(lock.map_or(OFF, |lock| lock.min(100)), Ordering::Relaxed);
+    }
+
+    // Percent per second, None lets the volume rise freely
+    pub fn slew_rate(&self) -> Option<u8> {
+        Some(self.slew_rate.load(Ordering::Relaxed)).filter(|&rate| rate > 0)
+    }
+
+    pub fn set_slew_rate(&self, rate: Option<u8>) {
+        self.slew_rate.store(rate.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    pub fn allow_mute(&self) -> bool {
+        self.allow_mute.load(Ordering::Relaxed)
+    }
+
+    pub fn set_allow_mute(&self, allow: bool) {
+        self.allow_mute.store(allow, Ordering::Relaxed);
+    }
+
+    // Whether there's anything for the engine to do at all
+    pub fn is_active(&self) -> bool {
+        self.ceiling().is_some() || self.max_volume().is_some() || self.floor().is_some() || self.lock().is_some() || self.slew_rate().is_some()
+    }
+
+    // Volume to hold given the current reading, lock beats ceiling, and the ceiling beats the floor when they cross
+    pub fn target(&self, state: &mut EngineState, volume: u8, elapsed: Duration) -> u8 {
+        if self.switched.swap(false, Ordering::Relaxed) {
+            *state = EngineState::new(volume);
+        }
+        if self.written.load(Ordering::Relaxed) != volume as u16 {
+            self.written.store(NO_WRITE, Ordering::Relaxed);
+        }
+        // The very first reading is whatever the volume was when the engine started, nobody pushed it, and neither
+        // did the app when it reads back what it set
+        let own = self.own_write.compare_exchange(volume as u16, NO_WRITE, Ordering::Relaxed, Ordering::Relaxed).is_ok();
+        let external = std::mem::replace(&mut state.started, true) && !own;
+        // Mute is a volume of 0 to the backend, so it's the one reading the floor and lock leave alone when allowed
+        if volume == 0 && self.allow_mute() {
+            state.slew.reset(0);
+            state.last_volume = 0;
+            return 0;
+        }
+        let ceiling = self.ceiling().unwrap_or(OFF).min(self.max_volume().unwrap_or(OFF));
+        if let Some(max_volume) = self.max_volume() && volume > max_volume {
+            // Amplification is refused outright, it never fades
+            self.over_amplified.fetch_max(volume, Ordering::Relaxed);
+            if external {
+                self.attempts.fetch_add(1, Ordering::Relaxed);
+            }
+            let target = max_volume.min(ceiling);
+            state.slew.reset(target);
+            state.last_volume = target;
+            return target;
+        }
+        if let Some(lock) = self.lock() {
+            let lock = lock.min(ceiling);
+            state.slew.reset(lock);
+            state.last_volume = lock;
+            return lock;
+        }
+        let requested = state.slew.requested(volume);
+        let capped = if requested > ceiling {
+            // Volume left behind by a falling ceiling fades out, anything pushed above it is clamped at once
+            if volume <= state.last_volume {
+                volume.saturating_sub(FADE_STEP).max(ceiling)
+            } else {
+                if external {
+                    self.attempts.fetch_add(1, Ordering::Relaxed);
+                }
+                ceiling
+            }
+        } else {
+            requested
+        };
+        // Rises, from other apps or our own slider, are spread into a ramp
+        let mut target = state.slew.step(capped, self.slew_rate().unwrap_or(0) as f32, elapsed);
+        if let Some(floor) = self.floor().map(|floor| floor.min(ceiling)) && target < floor {
+            target = floor;
+            state.slew.reset(floor);
+        }
+        state.last_volume = target;
+        target
+    }
+}
+
+fn optional(value: u8) -> Option<u8> {
+    (value != OFF).then_some(value)
+}
+
+fn optional_level(value: u8) -> Option<u8> {
+    (value != 0).then_some(value)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EngineState {
+    last_volume: u8,
+    slew: Slew,
+    started: bool,
+}
+
+impl EngineState {
+    pub fn new(volume: u8) -> Self {
+        Self {
+            // Starting from 0 makes a volume already above the ceiling clamp at once instead of fading
+            last_volume: 0,
+            slew: Slew::new(volume),
+            started: false,
+        }
+    }
+}
+
+// Background thread holding the system volume within the shared limits
+#[derive(Debug)]
+pub struct Engine {
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Engine {
+    pub fn start(limits: Arc<Limits>, direction: Direction) -> Self {
+        let (stop, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut state = EngineState::new(read_volume(direction).unwrap_or(0));
+            let mut last_tick = Instant::now();
+            let mut ticks: u32 = 0;
+            while rx.recv_timeout(TICK).is_err() {
+                ticks = ticks.wrapping_add(1);
+                let elapsed = last_tick.elapsed();
+                last_tick = Instant::now();
+                let Some(volume) = read_volume(direction) else {
+                    continue;
+                };
+                let target = limits.target(&mut state, volume, elapsed);
+                if target != volume {
+                    write_volume(direction, target);
+                    state.slew.set(target);
+                    limits.record_clamp(volume, target);
+                }
+                // The level above is an average, so an off-balance channel can still sit over the ceiling
+                if ticks.is_multiple_of(CHANNEL_CHECK_TICKS) && let Some(ceiling) = limits.ceiling() {
+                    cap_channels(direction, ceiling);
+                }
+            }
+        });
+        Self { stop, handle }
+    }
+
+    pub fn stop(self) {
+        let _ = self.stop.send(());
+        if let Err(error) = self.handle.join() {
+            eprintln!("Limiter engine panicked: {:?}", error);
+        }
+    }
+}
+
+// Output goes through cpvc like the rest of the app, input through the mixer CLI backend. Levels past 100% are
+// kept, so a ceiling or max volume sees the amplification
+fn read_volume(direction: Direction) -> Option<u8> {
+    match direction {
+        Direction::Output => Some(get_system_volume()),
+        Direction::Input => backend::volume(direction).ok().map(Volume::percent_u8),
+    }
+}
+
+fn write_volume(direction: Direction, volume: u8) {
+    match direction {
+        Direction::Output => {
+            set_system_volume(volume);
+        },
+        Direction::Input => {
+            if let Err(error) = backend::set_volume(direction, Volume::from(volume)) {
+                eprintln!("Failed to set input volume: {}", error);
+            }
+        },
+    }
+}
+
+fn cap_channels(direction: Direction, ceiling: u8) {
+    let Ok(channels) = backend::channels(direction) else {
+        return;
+    };
+    if let Some(capped) = balance::cap_channels(&channels, Volume::from(ceiling))
+        && let Err(error) = backend::set_channels(direction, &capped)
+    {
+        eprintln!("Failed to cap channel volumes: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Engine state after a first reading of `volume`, as if it had been running for a while
+    fn running(limits: &Limits, volume: u8) -> EngineState {
+        let mut state = EngineState::new(volume);
+        limits.target(&mut state, volume, TICK);
+        limits.take_attempts();
+        state
+    }
+
+    #[test]
+    fn mute_passes_the_floor_and_lock_when_allowed() {
+        let limits = Limits::default();
+        limits.set_floor(Some(20));
+        limits.set_lock(Some(40));
+        let mut state = running(&limits, 40);
+        assert_eq!(limits.target(&mut state, 0, TICK), 0);
+        limits.set_allow_mute(false);
+        limits.set_lock(None);
+        assert_eq!(limits.target(&mut state, 0, TICK), 20);
+    }
+
+    #[test]
+    fn lock_holds_the_volume_but_never_above_the_ceiling() {
+        let limits = Limits::default();
+        limits.set_lock(Some(50));
+        let mut state = running(&limits, 50);
+        assert_eq!(limits.target(&mut state, 10, TICK), 50);
+        assert_eq!(limits.target(&mut state, 90, TICK), 50);
+        limits.set_ceiling(Some(30));
+        assert_eq!(limits.target(&mut state, 50, TICK), 30);
+    }
+
+    #[test]
+    fn floor_is_capped_by_the_ceiling() {
+        let limits = Limits::default();
+        limits.set_floor(Some(40));
+        limits.set_ceiling(Some(30));
+        let mut state = running(&limits, 30);
+        assert_eq!(limits.target(&mut state, 10, TICK), 30);
+        limits.set_ceiling(Some(60));
+        assert_eq!(limits.target(&mut state, 10, TICK), 40);
+    }
+
+    #[test]
+    fn volume_fades_below_a_falling_ceiling() {
+        let limits = Limits::default();
+        let mut state = running(&limits, 50);
+        limits.set_ceiling(Some(45));
+        assert_eq!(limits.target(&mut state, 50, TICK), 49);
+        assert_eq!(limits.target(&mut state, 49, TICK), 48);
+        assert_eq!(limits.target(&mut state, 46, TICK), 45);
+        assert_eq!(limits.take_attempts(), 0);
+    }
+
+    #[test]
+    fn raises_past_the_ceiling_are_clamped_at_once() {
+        let limits = Limits::default();
+        limits.set_ceiling(Some(30));
+        let mut state = running(&limits, 20);
+        assert_eq!(limits.target(&mut state, 80, TICK), 30);
+        assert_eq!(limits.take_attempts(), 1);
+    }
+
+    #[test]
+    fn first_reading_above_the_ceiling_is_clamped_without_fading() {
+        let limits = Limits::default();
+        limits.set_ceiling(Some(30));
+        let mut state = EngineState::new(80);
+        assert_eq!(limits.target(&mut state, 80, TICK), 30);
+    }
+
+    #[test]
+    fn rises_under_the_ceiling_follow_the_slew_rate() {
+        let limits = Limits::default();
+        limits.set_ceiling(Some(80));
+        limits.set_slew_rate(Some(10));
+        let mut state = running(&limits, 20);
+        assert_eq!(limits.target(&mut state, 60, Duration::from_secs(1)), 30);
+        assert_eq!(limits.target(&mut state, 10, TICK), 10);
+    }
+
+    #[test]
+    fn only_readings_from_outside_count_as_attempts() {
+        let limits = Limits::default();
+        limits.set_ceiling(Some(30));
+        let mut state = EngineState::new(80);
+        // The level found at startup
+        limits.target(&mut state, 80, TICK);
+        assert_eq!(limits.take_attempts(), 0);
+        // The app's own write, read back once
+        limits.expect_write(60);
+        assert_eq!(limits.target(&mut state, 60, TICK), 30);
+        assert_eq!(limits.take_attempts(), 0);
+        limits.target(&mut state, 30, TICK);
+        assert_eq!(limits.target(&mut state, 60, TICK), 30);
+        assert_eq!(limits.take_attempts(), 1);
+        // Reading back the engine's own clamp
+        assert_eq!(limits.target(&mut state, 30, TICK), 30);
+        assert_eq!(limits.take_attempts(), 0);
+    }
+
+    #[test]
+    fn device_switch_takes_the_restored_level_as_a_first_reading() {
+        let limits = Limits::default();
+        limits.set_ceiling(Some(30));
+        let mut state = running(&limits, 20);
+        assert_eq!(limits.target(&mut state, 70, TICK), 30);
+        limits.device_switched();
+        assert_eq!(limits.take_attempts(), 0);
+        assert_eq!(limits.target(&mut state, 90, TICK), 30);
+        assert_eq!(limits.take_attempts(), 0);
+        limits.target(&mut state, 30, TICK);
+        limits.target(&mut state, 90, TICK);
+        assert_eq!(limits.take_attempts(), 1);
+    }
+
+    #[test]
+    fn amplification_is_refused_and_reported() {
+        let limits = Limits::default();
+        limits.set_max_volume(Some(120));
+        let mut state = running(&limits, 100);
+        assert_eq!(limits.target(&mut state, 110, TICK), 110);
+        assert_eq!(limits.take_over_amplified(), None);
+        assert_eq!(limits.target(&mut state, 150, TICK), 120);
+        assert_eq!(limits.target(&mut state, 130, TICK), 120);
+        assert_eq!(limits.take_over_amplified(), Some(150));
+        assert_eq!(limits.take_over_amplified(), None);
+        assert_eq!(limits.take_attempts(), 2);
+    }
+
+    #[test]
+    fn amplification_never_fades_and_stops_at_the_ceiling() {
+        let limits = Limits::default();
+        limits.set_max_volume(Some(150));
+        limits.set_ceiling(Some(60));
+        let mut state = running(&limits, 60);
+        limits.set_slew_rate(Some(1));
+        assert_eq!(limits.target(&mut state, 200, TICK), 60);
+    }
+
+    #[test]
+    fn max_volume_never_drops_below_full() {
+        let limits = Limits::default();
+        limits.set_max_volume(Some(80));
+        assert_eq!(limits.max_volume(), Some(100));
+        assert!(limits.is_active());
+    }
+
+    #[test]
+    fn clamps_report_the_first_and_latest_level() {
+        let limits = Limits::default();
+        limits.record_clamp(80, 50);
+        limits.record_clamp(50, 49);
+        assert_eq!(limits.take_clamp(), Some((80, 49)));
+        assert_eq!(limits.take_clamp(), None);
+    }
+
+    #[test]
+    fn engine_writes_are_known_until_the_volume_moves() {
+        let limits = Limits { ceiling: AtomicU8::new(50), ..Limits::default() };
+        let mut state = running(&limits, 50);
+        assert!(!limits.engine_wrote(50));
+        limits.record_clamp(80, 50);
+        assert!(limits.engine_wrote(50));
+        assert!(!limits.engine_wrote(80));
+        limits.target(&mut state, 50, TICK);
+        assert!(limits.engine_wrote(50));
+        // Somebody else moved it, even back to the same level it's theirs now
+        limits.target(&mut state, 40, TICK);
+        assert!(!limits.engine_wrote(40));
+        limits.target(&mut state, 50, TICK);
+        assert!(!limits.engine_wrote(50));
+    }
+}
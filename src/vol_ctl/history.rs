@@ -0,0 +1,390 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{NaiveDateTime, TimeDelta, Timelike};
+
+use crate::config;
+
+const HISTORY_FILE: &str = "history.log";
+const MINUTE_FORMAT: &str = "%Y-%m-%dT%H:%M";
+const KEEP_DAYS: i64 = 7;
+// The file is only appended to while running, it gets rewritten once about a day's worth of minutes has expired
+const REWRITE_AFTER: usize = 24 * 60;
+
+// How far back the dashboard looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Span {
+    #[default]
+    Hour,
+    Day,
+    Week,
+}
+
+impl Span {
+    pub const ALL: [Span; 3] = [Span::Hour, Span::Day, Span::Week];
+
+    pub fn length(self) -> TimeDelta {
+        match self {
+            Span::Hour => TimeDelta::hours(1),
+            Span::Day => TimeDelta::days(1),
+            Span::Week => TimeDelta::days(KEEP_DAYS),
+        }
+    }
+
+    // Graph resolution: minutes for the hour, ten minutes for the day, hours for the week
+    pub fn slices(self) -> usize {
+        match self {
+            Span::Hour => 60,
+            Span::Day => 144,
+            Span::Week => 168,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Span::Hour => write!(f, "Last hour"),
+            Span::Day => write!(f, "Last day"),
+            Span::Week => write!(f, "Last week"),
+        }
+    }
+}
+
+// One minute on one output device, stored as a single line:
+// `<minute> <highest volume> <lowest ceiling or -> <clamps> <seconds observed> <seconds at the ceiling> <device>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub minute: NaiveDateTime,
+    pub volume: u8,
+    // None when the limiter was off for the whole minute
+    pub ceiling: Option<u8>,
+    pub clamps: u16,
+    pub observed: Duration,
+    pub at_ceiling: Duration,
+    pub device: String,
+}
+
+impl Sample {
+    fn new(minute: NaiveDateTime, device: &str) -> Self {
+        Self {
+            minute,
+            volume: 0,
+            ceiling: None,
+            clamps: 0,
+            observed: Duration::ZERO,
+            at_ceiling: Duration::ZERO,
+            device: device.to_owned(),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {} {}",
+            self.minute.format(MINUTE_FORMAT),
+            self.volume,
+            self.ceiling.map_or(String::from("-"), |ceiling| ceiling.to_string()),
+            self.clamps,
+            self.observed.as_secs_f32().round(),
+            self.at_ceiling.as_secs_f32().round(),
+            self.device,
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(7, ' ');
+        let minute = NaiveDateTime::parse_from_str(fields.next()?, MINUTE_FORMAT).ok()?;
+        let volume = fields.next()?.parse().ok()?;
+        let ceiling = match fields.next()? {
+            "-" => None,
+            ceiling => Some(ceiling.parse().ok()?),
+        };
+        let clamps = fields.next()?.parse().ok()?;
+        let observed = Duration::from_secs(fields.next()?.parse().ok()?);
+        let at_ceiling = Duration::from_secs(fields.next()?.parse().ok()?);
+        let device = fields.next().unwrap_or_default().to_owned();
+        Some(Self { minute, volume, ceiling, clamps, observed, at_ceiling, device })
+    }
+}
+
+// A slice of a span for the graph, volume is None where nothing was recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bucket {
+    pub volume: Option<u8>,
+    pub ceiling: Option<u8>,
+    pub clamps: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Summary {
+    pub observed: Duration,
+    pub at_ceiling: Duration,
+    pub clamps: u32,
+    // Most listened first
+    pub devices: Vec<(String, Duration)>,
+}
+
+// Per-minute volume history of the last week
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    samples: VecDeque<Sample>,
+    current: Option<Sample>,
+    expired: usize,
+    // Where finished minutes are written, None for a history that wasn't loaded from the state directory
+    path: Option<PathBuf>,
+}
+
+fn truncate(time: NaiveDateTime) -> NaiveDateTime {
+    time.with_second(0).and_then(|time| time.with_nanosecond(0)).unwrap_or(time)
+}
+
+impl History {
+    pub fn path() -> Option<PathBuf> {
+        config::state_dir().map(|dir| dir.join(HISTORY_FILE))
+    }
+
+    // Finished minutes without touching the file, for checking what is built from them
+    #[cfg(test)]
+    pub(crate) fn from_samples(samples: impl IntoIterator<Item = Sample>) -> Self {
+        Self { samples: samples.into_iter().collect(), ..Self::default() }
+    }
+
+    // Lines that don't parse are skipped, anything older than a week is dropped from the file
+    pub fn load(now: NaiveDateTime) -> Self {
+        Self::load_from(Self::path(), now)
+    }
+
+    fn load_from(path: Option<PathBuf>, now: NaiveDateTime) -> Self {
+        let contents = path.as_ref().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+        let mut history = Self {
+            samples: contents.lines().filter_map(Sample::parse).collect(),
+            path,
+            ..Self::default()
+        };
+        if history.expire(now) > 0 && let Err(error) = history.rewrite() {
+            eprintln!("Failed to prune volume history: {}", error);
+        }
+        history
+    }
+
+    // Adds one poll of the output, minutes are written out once they are over
+    pub fn record(&mut self, now: NaiveDateTime, elapsed: Duration, volume: u8, ceiling: Option<u8>, clamped: bool, device: &str) -> io::Result<()> {
+        let minute = truncate(now);
+        let result = match &self.current {
+            Some(current) if current.minute != minute || current.device != device => self.flush(now),
+            _ => Ok(()),
+        };
+        let current = self.current.get_or_insert_with(|| Sample::new(minute, device));
+        current.volume = current.volume.max(volume);
+        current.ceiling = match (current.ceiling, ceiling) {
+            (Some(lowest), Some(ceiling)) => Some(lowest.min(ceiling)),
+            (lowest, ceiling) => lowest.or(ceiling),
+        };
+        current.clamps = current.clamps.saturating_add(clamped as u16);
+        current.observed += elapsed;
+        if ceiling.is_some_and(|ceiling| volume >= ceiling) {
+            current.at_ceiling += elapsed;
+        }
+        result
+    }
+
+    fn flush(&mut self, now: NaiveDateTime) -> io::Result<()> {
+        let Some(sample) = self.current.take() else {
+            return Ok(());
+        };
+        let line = sample.to_line();
+        self.samples.push_back(sample);
+        self.expire(now);
+        if self.expired >= REWRITE_AFTER {
+            return self.rewrite();
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(self.file()?)?;
+        writeln!(file, "{}", line)
+    }
+
+    fn expire(&mut self, now: NaiveDateTime) -> usize {
+        let start = now - TimeDelta::days(KEEP_DAYS);
+        let before = self.samples.len();
+        while self.samples.front().is_some_and(|sample| sample.minute < start) {
+            self.samples.pop_front();
+        }
+        let expired = before - self.samples.len();
+        self.expired += expired;
+        expired
+    }
+
+    fn file(&self) -> io::Result<&Path> {
+        let path = self.path.as_deref().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(path)
+    }
+
+    fn rewrite(&mut self) -> io::Result<()> {
+        let path = self.file()?;
+        let contents: String = self.samples.iter().map(|sample| sample.to_line() + "\n").collect();
+        fs::write(path, contents)?;
+        self.expired = 0;
+        Ok(())
+    }
+
+    // Everything from `start` on, including the minute still being recorded
+    pub fn since(&self, start: NaiveDateTime) -> impl Iterator<Item = &Sample> {
+        self.samples.iter().chain(self.current.as_ref()).filter(move |sample| sample.minute >= truncate(start))
+    }
+
+    // The span cut into `count` equal slices, oldest first
+    pub fn buckets(&self, span: Span, now: NaiveDateTime, count: usize) -> Vec<Bucket> {
+        let start = now - span.length();
+        let width = span.length().num_seconds() as f64 / count as f64;
+        let mut buckets = vec![Bucket::default(); count];
+        for sample in self.since(start) {
+            let index = ((sample.minute - start).num_seconds().max(0) as f64 / width) as usize;
+            let Some(bucket) = buckets.get_mut(index.min(count - 1)) else {
+                continue;
+            };
+            bucket.volume = Some(bucket.volume.map_or(sample.volume, |volume| volume.max(sample.volume)));
+            bucket.ceiling = match (bucket.ceiling, sample.ceiling) {
+                (Some(lowest), Some(ceiling)) => Some(lowest.min(ceiling)),
+                (lowest, ceiling) => lowest.or(ceiling),
+            };
+            bucket.clamps += sample.clamps as u32;
+        }
+        buckets
+    }
+
+    pub fn summary(&self, span: Span, now: NaiveDateTime) -> Summary {
+        let mut summary = Summary::default();
+        for sample in self.since(now - span.length()) {
+            summary.observed += sample.observed;
+            summary.at_ceiling += sample.at_ceiling;
+            summary.clamps += sample.clamps as u32;
+            match summary.devices.iter_mut().find(|(device, _)| *device == sample.device) {
+                Some((_, time)) => *time += sample.observed,
+                None => summary.devices.push((sample.device.clone(), sample.observed)),
+            }
+        }
+        summary.devices.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{at, temp_path};
+
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn sample(minute: NaiveDateTime, volume: u8, ceiling: Option<u8>, clamps: u16, device: &str) -> Sample {
+        Sample { minute, volume, ceiling, clamps, observed: 60 * SECOND, at_ceiling: Duration::ZERO, device: device.to_owned() }
+    }
+
+    #[test]
+    fn samples_survive_a_line_round_trip() {
+        let mut with_ceiling = sample(at(14, 20, 5), 72, Some(60), 3, "Built-in Audio Analog Stereo");
+        with_ceiling.at_ceiling = 12 * SECOND;
+        let without = sample(at(14, 20, 6), 40, None, 0, "");
+        for sample in [with_ceiling, without] {
+            assert_eq!(Sample::parse(&sample.to_line()), Some(sample));
+        }
+        assert_eq!(Sample::parse("2026-03-14T20:05 loud - 0 60 0 speakers"), None);
+        assert_eq!(Sample::parse(""), None);
+    }
+
+    #[test]
+    fn polls_within_a_minute_add_up() {
+        let mut history = History::default();
+        let now = at(14, 20, 5);
+        let _ = history.record(now, SECOND, 50, Some(70), false, "speakers");
+        let _ = history.record(now + TimeDelta::seconds(20), 2 * SECOND, 70, Some(60), true, "speakers");
+        let _ = history.record(now + TimeDelta::seconds(40), SECOND, 30, None, false, "speakers");
+        let current = history.since(now).collect::<Vec<_>>();
+        assert_eq!(current.len(), 1);
+        assert_eq!((current[0].volume, current[0].ceiling, current[0].clamps), (70, Some(60), 1));
+        assert_eq!((current[0].observed, current[0].at_ceiling), (4 * SECOND, 2 * SECOND));
+    }
+
+    #[test]
+    fn finished_minutes_are_appended_and_reloaded() {
+        let path = temp_path("history-append.log");
+        let _ = fs::remove_file(&path);
+        let mut history = History::load_from(Some(path.clone()), at(14, 20, 0));
+        history.record(at(14, 20, 0), SECOND, 50, Some(60), false, "speakers").unwrap();
+        // A new device starts a new sample within the same minute
+        history.record(at(14, 20, 0) + TimeDelta::seconds(30), SECOND, 40, None, false, "headphones").unwrap();
+        history.record(at(14, 20, 1), SECOND, 45, None, false, "headphones").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let reloaded = History::load_from(Some(path.clone()), at(14, 20, 2));
+        let devices = reloaded.since(at(14, 0, 0)).map(|sample| sample.device.as_str()).collect::<Vec<_>>();
+        assert_eq!(devices, ["speakers", "headphones"]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn loading_drops_samples_older_than_a_week_from_the_file() {
+        let path = temp_path("history-prune.log");
+        let lines = [sample(at(6, 12, 0), 50, None, 0, "old"), sample(at(7, 20, 0), 60, None, 0, "new")].map(|sample| sample.to_line() + "\n");
+        fs::write(&path, lines.concat() + "not a sample\n").unwrap();
+        let history = History::load_from(Some(path.clone()), at(14, 12, 0));
+        assert_eq!(history.since(at(1, 0, 0)).map(|sample| sample.device.as_str()).collect::<Vec<_>>(), ["new"]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), lines[1]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn histories_not_loaded_from_disk_keep_minutes_in_memory() {
+        let mut history = History::default();
+        history.record(at(14, 20, 0), SECOND, 50, None, false, "speakers").unwrap();
+        assert!(history.record(at(14, 20, 1), SECOND, 50, None, false, "speakers").is_err());
+        assert_eq!(history.since(at(14, 20, 0)).count(), 2);
+    }
+
+    #[test]
+    fn buckets_hold_the_loudest_volume_and_lowest_ceiling_of_their_slice() {
+        let now = at(14, 21, 0);
+        let history = History::from_samples([
+            sample(at(14, 19, 59), 90, Some(10), 9, "speakers"),
+            sample(at(14, 20, 0), 50, Some(70), 1, "speakers"),
+            sample(at(14, 20, 1), 65, Some(60), 2, "speakers"),
+            sample(at(14, 20, 30), 40, None, 0, "speakers"),
+            sample(at(14, 20, 59), 30, Some(80), 0, "speakers"),
+        ]);
+        let buckets = history.buckets(Span::Hour, now, 30);
+        assert_eq!(buckets.len(), 30);
+        assert_eq!(buckets[0], Bucket { volume: Some(65), ceiling: Some(60), clamps: 3 });
+        assert_eq!(buckets[1], Bucket::default());
+        assert_eq!(buckets[15], Bucket { volume: Some(40), ceiling: None, clamps: 0 });
+        assert_eq!(buckets[29], Bucket { volume: Some(30), ceiling: Some(80), clamps: 0 });
+        assert_eq!(buckets.iter().filter(|bucket| bucket.volume.is_some()).count(), 3);
+    }
+
+    #[test]
+    fn summary_adds_up_the_span_and_ranks_devices() {
+        let now = at(14, 21, 0);
+        let mut loud = sample(at(14, 20, 10), 80, Some(60), 4, "headphones");
+        loud.at_ceiling = 30 * SECOND;
+        let mut history = History::from_samples([
+            sample(at(14, 12, 0), 50, None, 7, "speakers"),
+            sample(at(14, 20, 5), 50, None, 0, "speakers"),
+            loud.clone(),
+            loud,
+        ]);
+        // The minute still being recorded counts too
+        let _ = history.record(now, 20 * SECOND, 40, Some(60), true, "speakers");
+        let summary = history.summary(Span::Hour, now);
+        assert_eq!(summary.observed, 200 * SECOND);
+        assert_eq!(summary.at_ceiling, 60 * SECOND);
+        assert_eq!(summary.clamps, 9);
+        assert_eq!(summary.devices, [(String::from("headphones"), 120 * SECOND), (String::from("speakers"), 80 * SECOND)]);
+        assert_eq!(history.summary(Span::Day, now).clamps, 16);
+    }
+}
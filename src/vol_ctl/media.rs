@@ -0,0 +1,24 @@
+use std::{io, process::Command};
+
+// Asks every media player to pause, MPRIS players through playerctl on Linux
+pub fn pause_media() -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        let status = Command::new("playerctl").args(["--all-players", "pause"]).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("playerctl exited with {}", status)))
+        }
+    } else if cfg!(target_os = "macos") {
+        let status = Command::new("osascript")
+            .args(["-e", "tell application \"Music\" to if it is running then pause"])
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("osascript exited with {}", status)))
+        }
+    } else {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "pausing media is not supported on this platform"))
+    }
+}
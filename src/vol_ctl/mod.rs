@@ -1,4 +1,16 @@
-struct VolumeControls {
-    vol: u8,
-    vol_str: String,
-}
\ No newline at end of file
+pub mod backend;
+pub mod balance;
+pub mod curve;
+pub mod device;
+pub mod dosimeter;
+pub mod engine;
+pub mod history;
+pub mod media;
+pub mod notify;
+pub mod overrides;
+pub mod ramp;
+pub mod schedule;
+pub mod sleep_timer;
+pub mod slew;
+pub mod tamper;
+pub mod volumes;
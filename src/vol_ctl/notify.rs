@@ -0,0 +1,18 @@
+use std::{io, process::Command};
+
+// Desktop notification through notify-send on Linux and osascript on macOS
+pub fn notify(title: &str, body: &str) -> io::Result<()> {
+    let status = if cfg!(target_os = "linux") {
+        Command::new("notify-send").args(["--app-name", "Volume Limiter", title, body]).status()?
+    } else if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title {:?}", body, title);
+        Command::new("osascript").args(["-e", &script]).status()?
+    } else {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "notifications are not supported on this platform"));
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("notifier exited with {}", status)))
+    }
+}
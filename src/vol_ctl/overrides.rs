@@ -0,0 +1,116 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const OVERRIDE_FILE: &str = "override.toml";
+
+// A ceiling granted for a while in place of the usual one, kept on disk so a restart doesn't end it early or make it last forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Override {
+    pub percent: u8,
+    pub granted: NaiveDateTime,
+    pub until: NaiveDateTime,
+}
+
+impl Override {
+    pub fn new(percent: u8, minutes: u8, now: NaiveDateTime) -> Self {
+        Self {
+            percent: percent.min(100),
+            granted: now,
+            until: now + Duration::minutes(minutes as i64),
+        }
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.granted <= now && now < self.until
+    }
+
+    // None once it has run out
+    pub fn ceiling(&self, now: NaiveDateTime) -> Option<u8> {
+        self.is_active(now).then_some(self.percent)
+    }
+
+    pub fn remaining(&self, now: NaiveDateTime) -> Duration {
+        (self.until - now).max(Duration::zero())
+    }
+
+    fn path() -> Option<PathBuf> {
+        config::state_dir().map(|dir| dir.join(OVERRIDE_FILE))
+    }
+
+    pub fn load(now: NaiveDateTime) -> Option<Self> {
+        Self::load_from(&Self::path()?, now)
+    }
+
+    // Anything that already ran out is dropped, along with its file
+    fn load_from(path: &Path, now: NaiveDateTime) -> Option<Self> {
+        let loaded = fs::read_to_string(path).ok().and_then(|contents| toml::from_str::<Self>(&contents).ok());
+        match loaded {
+            Some(grant) if grant.is_active(now) => Some(grant),
+            Some(_) => {
+                let _ = remove(path);
+                None
+            },
+            None => None,
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&Self::path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?)
+    }
+
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self).map_err(io::Error::other)?)
+    }
+
+    pub fn clear() -> io::Result<()> {
+        Self::path().map_or(Ok(()), |path| remove(&path))
+    }
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{at, temp_path};
+
+    use super::*;
+
+    #[test]
+    fn restart_keeps_an_active_override() {
+        let path = temp_path("override-active.toml");
+        let grant = Override::new(60, 30, at(14, 20, 0));
+        grant.save_to(&path).unwrap();
+        assert_eq!(Override::load_from(&path, at(14, 20, 10)), Some(grant));
+        assert_eq!(Override::load_from(&path, at(14, 20, 10)).and_then(|grant| grant.ceiling(at(14, 20, 10))), Some(60));
+        remove(&path).unwrap();
+    }
+
+    #[test]
+    fn restart_after_the_end_drops_the_override_and_its_file() {
+        let path = temp_path("override-expired.toml");
+        Override::new(60, 30, at(14, 20, 0)).save_to(&path).unwrap();
+        assert_eq!(Override::load_from(&path, at(14, 20, 30)), None);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn percent_is_capped_and_remaining_never_negative() {
+        let grant = Override::new(150, 10, at(14, 8, 0));
+        assert_eq!(grant.percent, 100);
+        assert_eq!(grant.remaining(at(14, 8, 4)), Duration::minutes(6));
+        assert_eq!(grant.remaining(at(14, 9, 0)), Duration::zero());
+        assert!(!grant.is_active(at(14, 7, 59)));
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+// Ceiling that slides from `from` at `start` to `to` at `end`, then holds `to` until `until`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ramp {
+    pub enabled: bool,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub until: NaiveTime,
+    pub from: u8,
+    pub to: u8,
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        Self {
+            enabled: false,
+            start: time(19),
+            end: time(22),
+            until: time(7),
+            from: 60,
+            to: 20,
+        }
+    }
+}
+
+impl Ramp {
+    pub fn ceiling_at(&self, now: NaiveDateTime) -> Option<u8> {
+        if !self.enabled {
+            return None;
+        }
+        // Everything is measured from the start of the ramp, so ranges past midnight need no special case
+        let since = |time: NaiveTime| (time - self.start).num_seconds().rem_euclid(TimeDelta::days(1).num_seconds());
+        let elapsed = since(now.time());
+        let fade = since(self.end);
+        let hold = since(self.until);
+        if elapsed < fade {
+            let progress = elapsed as f32 / fade as f32;
+            Some((self.from as f32 + (self.to as f32 - self.from as f32) * progress).round() as u8)
+        } else if elapsed < hold.max(fade) {
+            Some(self.to)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::at;
+
+    use super::*;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn enabled() -> Ramp {
+        Ramp { enabled: true, ..Ramp::default() }
+    }
+
+    #[test]
+    fn disabled_ramp_has_no_ceiling() {
+        assert_eq!(Ramp::default().ceiling_at(at(16, 20, 0)), None);
+    }
+
+    #[test]
+    fn fades_from_start_to_end() {
+        let ramp = enabled();
+        assert_eq!(ramp.ceiling_at(at(16, 18, 59)), None);
+        assert_eq!(ramp.ceiling_at(at(16, 19, 0)), Some(60));
+        assert_eq!(ramp.ceiling_at(at(16, 20, 30)), Some(40));
+        assert_eq!(ramp.ceiling_at(at(16, 22, 0)), Some(20));
+    }
+
+    #[test]
+    fn holds_past_midnight_until_morning() {
+        let ramp = enabled();
+        assert_eq!(ramp.ceiling_at(at(16, 0, 0)), Some(20));
+        assert_eq!(ramp.ceiling_at(at(16, 6, 59)), Some(20));
+        assert_eq!(ramp.ceiling_at(at(16, 7, 0)), None);
+        assert_eq!(ramp.ceiling_at(at(16, 12, 0)), None);
+    }
+
+    #[test]
+    fn fade_can_cross_midnight() {
+        let ramp = Ramp { start: time(23), end: time(1), until: time(6), from: 50, to: 10, ..enabled() };
+        assert_eq!(ramp.ceiling_at(at(16, 23, 0)), Some(50));
+        assert_eq!(ramp.ceiling_at(at(16, 0, 0)), Some(30));
+        assert_eq!(ramp.ceiling_at(at(16, 3, 0)), Some(10));
+        assert_eq!(ramp.ceiling_at(at(16, 6, 0)), None);
+    }
+
+    #[test]
+    fn no_fade_jumps_straight_to_the_end_level() {
+        let ramp = Ramp { start: time(21), end: time(21), until: time(23), ..enabled() };
+        assert_eq!(ramp.ceiling_at(at(16, 21, 0)), Some(20));
+        assert_eq!(ramp.ceiling_at(at(16, 22, 59)), Some(20));
+        assert_eq!(ramp.ceiling_at(at(16, 23, 0)), None);
+    }
+
+    #[test]
+    fn hold_ending_before_the_fade_stops_at_the_end() {
+        let ramp = Ramp { until: time(21), ..enabled() };
+        assert_eq!(ramp.ceiling_at(at(16, 21, 30)), Some(27));
+        assert_eq!(ramp.ceiling_at(at(16, 22, 0)), None);
+    }
+}
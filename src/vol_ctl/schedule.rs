@@ -0,0 +1,234 @@
+use std::fmt::{self, Debug, Display};
+
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+pub const WEEK: [Weekday; 7] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+pub const WEEKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+pub const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+// Source of the current local time, swapped out for a fixed clock when checking schedules
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub NaiveDateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> NaiveDateTime {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    Limit(u8),
+    Unlimited,
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleAction::Limit(percent) => write!(f, "{}%", percent),
+            RuleAction::Unlimited => write!(f, "no limit"),
+        }
+    }
+}
+
+// A range ending at or before its start runs past midnight, `days` are the days the range starts on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub action: RuleAction,
+    // Only applies while this output device is the default one
+    pub device: Option<String>,
+}
+
+impl Rule {
+    pub fn new(name: impl Into<String>, days: &[Weekday], start: NaiveTime, end: NaiveTime, action: RuleAction) -> Self {
+        Self {
+            name: name.into(),
+            days: days.to_vec(),
+            start,
+            end,
+            action,
+            device: None,
+        }
+    }
+
+    pub fn device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn matches(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        if !self.wraps() {
+            self.days.contains(&now.weekday()) && self.start <= time && time < self.end
+        } else if time >= self.start {
+            self.days.contains(&now.weekday())
+        } else {
+            // Early-morning part of a range that started the day before
+            time < self.end && self.days.contains(&(now - Duration::days(1)).weekday())
+        }
+    }
+
+    pub fn applies_to(&self, device: &str) -> bool {
+        self.device.as_deref().is_none_or(|rule_device| rule_device == device)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub enabled: bool,
+    pub rules: Vec<Rule>,
+}
+
+impl Schedule {
+    // Quiet weeknights, a moderate daytime limit and unlimited weekends
+    pub fn quiet_hours() -> Vec<Rule> {
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        vec![
+            Rule::new("Weeknight quiet hours", &WEEKDAYS, time(21), time(7), RuleAction::Limit(15)),
+            Rule::new("Weekday daytime", &WEEKDAYS, time(7), time(21), RuleAction::Limit(60)),
+            Rule::new("Weekend", &WEEKEND, time(7), time(7), RuleAction::Unlimited),
+        ]
+    }
+
+    // Rules for the given device win over device-independent ones, otherwise the first matching rule in the list is used
+    pub fn active(&self, now: NaiveDateTime, device: &str) -> Option<(usize, &Rule)> {
+        if !self.enabled {
+            return None;
+        }
+        let matching = |device_specific: bool| {
+            self.rules.iter().enumerate().find(|(_, rule)| {
+                rule.device.is_some() == device_specific && rule.applies_to(device) && rule.matches(now)
+            })
+        };
+        matching(true).or_else(|| matching(false))
+    }
+
+    pub fn active_at(&self, clock: &dyn Clock, device: &str) -> Option<(usize, &Rule)> {
+        self.active(clock.now(), device)
+    }
+}
+
+pub fn parse_time(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input.trim(), "%H:%M").ok()
+}
+
+pub fn format_time(time: NaiveTime) -> String {
+    time.format("%H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util;
+
+    use super::*;
+
+    // 2026-03-16 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> FixedClock {
+        FixedClock(test_util::at(day, hour, minute))
+    }
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn schedule(rules: Vec<Rule>) -> Schedule {
+        Schedule { enabled: true, rules }
+    }
+
+    #[test]
+    fn daytime_range_excludes_its_end() {
+        let rule = Rule::new("Day", &[Weekday::Mon], time(9), time(17), RuleAction::Limit(50));
+        assert_eq!(at(16, 0, 0).now().weekday(), Weekday::Mon);
+        assert!(!rule.matches(at(16, 8, 59).now()));
+        assert!(rule.matches(at(16, 9, 0).now()));
+        assert!(rule.matches(at(16, 16, 59).now()));
+        assert!(!rule.matches(at(16, 17, 0).now()));
+        assert!(!rule.matches(at(17, 12, 0).now()));
+    }
+
+    #[test]
+    fn overnight_range_wraps_past_midnight() {
+        let rule = Rule::new("Night", &[Weekday::Mon], time(21), time(7), RuleAction::Limit(15));
+        assert!(rule.wraps());
+        assert!(!rule.matches(at(16, 20, 59).now()));
+        assert!(rule.matches(at(16, 21, 0).now()));
+        assert!(rule.matches(at(16, 23, 59).now()));
+        // Tuesday morning is still Monday night
+        assert!(rule.matches(at(17, 0, 0).now()));
+        assert!(rule.matches(at(17, 6, 59).now()));
+        assert!(!rule.matches(at(17, 7, 0).now()));
+    }
+
+    #[test]
+    fn overnight_range_checks_the_previous_weekday() {
+        let rule = Rule::new("Night", &[Weekday::Mon], time(21), time(7), RuleAction::Limit(15));
+        // Monday morning belongs to Sunday night, which isn't in the rule
+        assert!(!rule.matches(at(16, 3, 0).now()));
+        // Tuesday night starts on a day that isn't in the rule either
+        assert!(!rule.matches(at(17, 22, 0).now()));
+        // Sunday night wraps into the next week's Monday
+        let sunday = Rule::new("Sunday night", &[Weekday::Sun], time(22), time(6), RuleAction::Limit(10));
+        assert!(sunday.matches(at(16, 5, 0).now()));
+    }
+
+    #[test]
+    fn equal_start_and_end_covers_the_whole_day() {
+        let rule = Rule::new("All day", &[Weekday::Sat], time(7), time(7), RuleAction::Unlimited);
+        // 2026-03-21 is a Saturday
+        assert!(rule.matches(at(21, 7, 0).now()));
+        assert!(rule.matches(at(22, 6, 59).now()));
+        assert!(!rule.matches(at(22, 7, 0).now()));
+    }
+
+    #[test]
+    fn device_rules_win_over_general_ones() {
+        let general = Rule::new("General", &WEEK, time(0), time(0), RuleAction::Limit(60));
+        let speakers = Rule::new("Speakers", &WEEK, time(20), time(8), RuleAction::Limit(20)).device("speakers");
+        let schedule = schedule(vec![general, speakers]);
+        assert_eq!(schedule.active_at(&at(16, 22, 0), "speakers").map(|(index, _)| index), Some(1));
+        assert_eq!(schedule.active_at(&at(16, 22, 0), "headphones").map(|(index, _)| index), Some(0));
+        assert_eq!(schedule.active_at(&at(16, 12, 0), "speakers").map(|(index, _)| index), Some(0));
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_disabled_schedule_matches_nothing() {
+        let mut schedule = schedule(Schedule::quiet_hours());
+        assert_eq!(schedule.active_at(&at(16, 23, 0), "").map(|(_, rule)| rule.action), Some(RuleAction::Limit(15)));
+        assert_eq!(schedule.active_at(&at(16, 12, 0), "").map(|(_, rule)| rule.action), Some(RuleAction::Limit(60)));
+        // Friday night's quiet hours run into Saturday morning, before the weekend rule starts
+        assert_eq!(schedule.active_at(&at(21, 6, 0), "").map(|(_, rule)| rule.action), Some(RuleAction::Limit(15)));
+        assert_eq!(schedule.active_at(&at(21, 12, 0), "").map(|(_, rule)| rule.action), Some(RuleAction::Unlimited));
+        schedule.enabled = false;
+        assert_eq!(schedule.active_at(&at(16, 23, 0), ""), None);
+    }
+
+    #[test]
+    fn times_parse_and_format_as_hours_and_minutes() {
+        assert_eq!(parse_time(" 07:30 "), NaiveTime::from_hms_opt(7, 30, 0));
+        assert_eq!(parse_time("7.30"), None);
+        assert_eq!(format_time(time(21)), "21:00");
+    }
+}
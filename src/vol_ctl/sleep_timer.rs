@@ -0,0 +1,181 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AfterSleep {
+    #[default]
+    Nothing,
+    Mute,
+    Pause,
+}
+
+impl AfterSleep {
+    pub const ALL: [AfterSleep; 3] = [AfterSleep::Nothing, AfterSleep::Mute, AfterSleep::Pause];
+}
+
+impl Display for AfterSleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AfterSleep::Nothing => write!(f, "nothing"),
+            AfterSleep::Mute => write!(f, "mute"),
+            AfterSleep::Pause => write!(f, "pause"),
+        }
+    }
+}
+
+impl FromStr for AfterSleep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AfterSleep::ALL
+            .into_iter()
+            .find(|after| after.to_string() == s.to_lowercase())
+            .ok_or_else(|| format!("expected one of nothing, mute or pause, got \"{}\"", s))
+    }
+}
+
+// What the GUI offers when starting a timer, remembered between runs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SleepSettings {
+    pub minutes: u8,
+    pub fade_minutes: u8,
+    pub floor: u8,
+    pub after: AfterSleep,
+}
+
+impl Default for SleepSettings {
+    fn default() -> Self {
+        Self {
+            minutes: 30,
+            fade_minutes: 10,
+            floor: 0,
+            after: AfterSleep::Nothing,
+        }
+    }
+}
+
+impl SleepSettings {
+    pub fn timer(&self, now: Instant) -> SleepTimer {
+        SleepTimer::new(now, minutes(self.minutes), minutes(self.fade_minutes), self.floor, self.after)
+    }
+}
+
+fn minutes(minutes: u8) -> Duration {
+    Duration::from_secs(minutes as u64 * 60)
+}
+
+// Fades from whatever the volume is when the fade begins down to `floor`, reaching it when the timer runs out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepTimer {
+    started: Instant,
+    duration: Duration,
+    fade: Duration,
+    pub floor: u8,
+    pub after: AfterSleep,
+    from: Option<u8>,
+}
+
+impl SleepTimer {
+    pub fn new(started: Instant, duration: Duration, fade: Duration, floor: u8, after: AfterSleep) -> Self {
+        Self {
+            started,
+            duration,
+            fade: fade.min(duration),
+            floor,
+            after,
+            from: None,
+        }
+    }
+
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.duration.saturating_sub(now - self.started)
+    }
+
+    pub fn is_fading(&self, now: Instant) -> bool {
+        self.remaining(now) < self.fade
+    }
+
+    pub fn is_done(&self, now: Instant) -> bool {
+        self.remaining(now).is_zero()
+    }
+
+    // Volume the fade asks for at `now`, None until it starts; it never asks for more than `volume`
+    pub fn target(&mut self, now: Instant, volume: u8) -> Option<u8> {
+        if !self.is_fading(now) {
+            return None;
+        }
+        let from = *self.from.get_or_insert(volume);
+        if from <= self.floor {
+            return Some(volume.min(from));
+        }
+        let progress = if self.fade.is_zero() {
+            1.0
+        } else {
+            1.0 - self.remaining(now).as_secs_f32() / self.fade.as_secs_f32()
+        };
+        let target = (from as f32 - (from - self.floor) as f32 * progress).round() as u8;
+        Some(volume.min(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn timer(start: Instant, fade_minutes: u8, floor: u8) -> SleepTimer {
+        SleepSettings { minutes: 10, fade_minutes, floor, after: AfterSleep::Mute }.timer(start)
+    }
+
+    #[test]
+    fn fade_runs_from_the_volume_it_starts_at_down_to_the_floor() {
+        let start = Instant::now();
+        let mut timer = timer(start, 4, 20);
+        assert_eq!(timer.target(start + 5 * MINUTE, 80), None);
+        assert_eq!(timer.target(start + 6 * MINUTE + SECOND, 80), Some(80));
+        assert_eq!(timer.target(start + 7 * MINUTE, 80), Some(65));
+        assert_eq!(timer.target(start + 8 * MINUTE, 65), Some(50));
+        assert_eq!(timer.target(start + 10 * MINUTE, 50), Some(20));
+        assert!(timer.is_done(start + 10 * MINUTE));
+    }
+
+    #[test]
+    fn fade_never_raises_a_volume_turned_down_meanwhile() {
+        let start = Instant::now();
+        let mut timer = timer(start, 4, 20);
+        assert_eq!(timer.target(start + 6 * MINUTE + SECOND, 80), Some(80));
+        assert_eq!(timer.target(start + 8 * MINUTE, 30), Some(30));
+    }
+
+    #[test]
+    fn volume_already_at_or_below_the_floor_is_left_alone() {
+        let start = Instant::now();
+        let mut below = timer(start, 4, 20);
+        assert_eq!(below.target(start + 6 * MINUTE + SECOND, 15), Some(15));
+        assert_eq!(below.target(start + 9 * MINUTE, 18), Some(15));
+        assert_eq!(below.target(start + 10 * MINUTE, 10), Some(10));
+        let mut at_floor = timer(start, 4, 20);
+        assert_eq!(at_floor.target(start + 8 * MINUTE, 20), Some(20));
+        assert_eq!(at_floor.target(start + 9 * MINUTE, 20), Some(20));
+    }
+
+    #[test]
+    fn fade_is_capped_by_the_timer_and_may_be_left_out() {
+        let start = Instant::now();
+        let mut whole = timer(start, 30, 0);
+        assert_eq!(whole.target(start, 60), None);
+        assert_eq!(whole.target(start + SECOND, 60), Some(60));
+        assert_eq!(whole.target(start + 5 * MINUTE, 60), Some(30));
+        let mut without = timer(start, 0, 10);
+        assert_eq!(without.target(start + 9 * MINUTE, 60), None);
+        assert_eq!(without.target(start + 10 * MINUTE, 60), None);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+// Caps how fast the volume may rise, decreases pass straight through
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Slew {
+    level: f32,
+    // Volume that was asked for while the ramp is still catching up to it
+    goal: Option<u8>,
+    last_set: Option<u8>,
+}
+
+impl Slew {
+    pub fn new(volume: u8) -> Self {
+        Self {
+            level: volume as f32,
+            goal: None,
+            last_set: None,
+        }
+    }
+
+    // The volume asked for, looking through the steps of a ramp we set ourselves
+    pub fn requested(&self, volume: u8) -> u8 {
+        match (self.goal, self.last_set) {
+            (Some(goal), Some(set)) if set == volume => goal,
+            _ => volume,
+        }
+    }
+
+    // Next volume on the way to `requested`, rising at most `rate` percent per second
+    pub fn step(&mut self, requested: u8, rate: f32, elapsed: Duration) -> u8 {
+        let requested_level = requested as f32;
+        if requested_level <= self.level || rate <= 0.0 {
+            self.level = requested_level;
+            self.goal = None;
+            return requested;
+        }
+        let allowed = self.level + rate * elapsed.as_secs_f32();
+        if requested_level <= allowed {
+            self.level = requested_level;
+            self.goal = None;
+            requested
+        } else {
+            self.level = allowed;
+            self.goal = Some(requested);
+            allowed.floor() as u8
+        }
+    }
+
+    // Drops any ramp in progress, e.g. when something else decided the volume
+    pub fn reset(&mut self, volume: u8) {
+        self.level = volume as f32;
+        self.goal = None;
+    }
+
+    // Remembers what was written to the system so the next reading can be recognised as our own
+    pub fn set(&mut self, volume: u8) {
+        self.last_set = Some(volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn decreases_pass_straight_through() {
+        let mut slew = Slew::new(80);
+        assert_eq!(slew.step(10, 5.0, SECOND), 10);
+    }
+
+    #[test]
+    fn increases_rise_at_the_rate() {
+        let mut slew = Slew::new(20);
+        assert_eq!(slew.step(80, 10.0, SECOND), 30);
+        assert_eq!(slew.step(80, 10.0, Duration::from_millis(500)), 35);
+        assert_eq!(slew.step(80, 10.0, Duration::from_secs(10)), 80);
+    }
+
+    #[test]
+    fn fractional_steps_add_up() {
+        let mut slew = Slew::new(20);
+        let steps: Vec<u8> = (0..4).map(|_| slew.step(80, 1.0, Duration::from_millis(500))).collect();
+        assert_eq!(steps, [20, 21, 21, 22]);
+    }
+
+    #[test]
+    fn zero_rate_disables_the_cap() {
+        let mut slew = Slew::new(20);
+        assert_eq!(slew.step(80, 0.0, SECOND), 80);
+    }
+
+    #[test]
+    fn own_steps_are_read_back_as_the_goal() {
+        let mut slew = Slew::new(20);
+        let stepped = slew.step(80, 10.0, SECOND);
+        slew.set(stepped);
+        assert_eq!(slew.requested(stepped), 80);
+        // Anything else was set by someone else
+        assert_eq!(slew.requested(50), 50);
+    }
+
+    #[test]
+    fn reset_drops_the_ramp() {
+        let mut slew = Slew::new(20);
+        let stepped = slew.step(80, 10.0, SECOND);
+        slew.set(stepped);
+        slew.reset(40);
+        assert_eq!(slew.requested(stepped), stepped);
+        assert_eq!(slew.step(60, 10.0, SECOND), 50);
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+// What happens once something outside the app keeps pushing the volume past the ceiling
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TamperSettings {
+    pub enabled: bool,
+    // Attempts within the window that trigger the actions
+    pub threshold: u8,
+    pub window_minutes: u8,
+    pub notify: bool,
+    pub mute: bool,
+    pub mute_minutes: u8,
+    pub lower: bool,
+    // Percent taken off the limit each time
+    pub lower_by: u8,
+}
+
+impl Default for TamperSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 5,
+            window_minutes: 10,
+            notify: true,
+            mute: false,
+            mute_minutes: 5,
+            lower: false,
+            lower_by: 10,
+        }
+    }
+}
+
+impl TamperSettings {
+    pub fn window(&self) -> Duration {
+        Duration::minutes(self.window_minutes as i64)
+    }
+}
+
+// Attempts to go past the ceiling, as the limiter engine reported them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TamperMonitor {
+    recent: VecDeque<NaiveDateTime>,
+    total: u32,
+    escalations: u32,
+}
+
+impl TamperMonitor {
+    // Records an attempt, true when it crosses the threshold so the caller should escalate
+    pub fn record(&mut self, now: NaiveDateTime, settings: &TamperSettings) -> bool {
+        self.total += 1;
+        self.recent.push_back(now);
+        self.prune(now, settings.window());
+        if settings.enabled && self.recent.len() >= settings.threshold.max(1) as usize {
+            // Starting the count over keeps one burst from escalating on every further attempt
+            self.recent.clear();
+            self.escalations += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn recent(&self, now: NaiveDateTime, window: Duration) -> usize {
+        self.recent.iter().filter(|&&attempt| now - attempt < window).count()
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    pub fn escalations(&self) -> u32 {
+        self.escalations
+    }
+
+    fn prune(&mut self, now: NaiveDateTime, window: Duration) {
+        while self.recent.front().is_some_and(|&attempt| now - attempt >= window) {
+            self.recent.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::at;
+
+    use super::*;
+
+    fn enabled(threshold: u8) -> TamperSettings {
+        TamperSettings { enabled: true, threshold, ..TamperSettings::default() }
+    }
+
+    #[test]
+    fn escalates_once_the_threshold_is_reached() {
+        let (mut monitor, settings) = (TamperMonitor::default(), enabled(3));
+        assert!(!monitor.record(at(16, 20, 0), &settings));
+        assert!(!monitor.record(at(16, 20, 1), &settings));
+        assert!(monitor.record(at(16, 20, 2), &settings));
+        assert_eq!((monitor.total(), monitor.escalations()), (3, 1));
+    }
+
+    #[test]
+    fn count_starts_over_after_escalating() {
+        let (mut monitor, settings) = (TamperMonitor::default(), enabled(2));
+        assert!(!monitor.record(at(16, 20, 0), &settings));
+        assert!(monitor.record(at(16, 20, 0), &settings));
+        assert!(!monitor.record(at(16, 20, 1), &settings));
+        assert_eq!(monitor.recent(at(16, 20, 1), settings.window()), 1);
+    }
+
+    #[test]
+    fn attempts_outside_the_window_are_forgotten() {
+        let (mut monitor, settings) = (TamperMonitor::default(), enabled(2));
+        assert!(!monitor.record(at(16, 20, 0), &settings));
+        // The window is 10 minutes, the first attempt drops out right at its end
+        assert!(!monitor.record(at(16, 20, 10), &settings));
+        assert!(monitor.record(at(16, 20, 19), &settings));
+    }
+
+    #[test]
+    fn disabled_monitor_only_counts() {
+        let (mut monitor, settings) = (TamperMonitor::default(), TamperSettings { threshold: 1, ..TamperSettings::default() });
+        assert!(!monitor.record(at(16, 20, 0), &settings));
+        assert!(!monitor.record(at(16, 20, 1), &settings));
+        assert_eq!((monitor.total(), monitor.escalations()), (2, 0));
+    }
+
+    #[test]
+    fn zero_threshold_acts_like_one() {
+        let (mut monitor, settings) = (TamperMonitor::default(), enabled(0));
+        assert!(monitor.record(at(16, 20, 0), &settings));
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::config;
+
+const VOLUMES_FILE: &str = "volumes.toml";
+
+// Volume of one output device as shown in the controls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VolumeControls {
+    pub vol: u8,
+    pub vol_str: String,
+    // Last level the user picked on this device, restored when it becomes active again
+    pub chosen: Option<u8>,
+    // Last level we wrote ourselves, so readings of it aren't mistaken for a user choice
+    pub set: Option<u8>,
+}
+
+impl VolumeControls {
+    pub fn show(&mut self, vol: u8) {
+        self.vol = vol;
+        self.vol_str = vol.to_string();
+    }
+
+    pub fn choose(&mut self, vol: u8) {
+        self.show(vol);
+        self.chosen = Some(vol);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceVolumes {
+    devices: BTreeMap<String, VolumeControls>,
+}
+
+impl DeviceVolumes {
+    pub fn path() -> Option<PathBuf> {
+        config::state_dir().map(|dir| dir.join(VOLUMES_FILE))
+    }
+
+    pub fn load() -> Self {
+        let chosen: BTreeMap<String, u8> = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            devices: chosen
+                .into_iter()
+                .map(|(device, vol)| (device, VolumeControls { chosen: Some(vol), ..VolumeControls::default() }))
+                .collect(),
+        }
+    }
+
+    // Only the chosen levels are kept, everything else is read back from the system
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no state directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let chosen: BTreeMap<&String, u8> = self
+            .devices
+            .iter()
+            .filter_map(|(device, controls)| controls.chosen.map(|vol| (device, vol)))
+            .collect();
+        fs::write(path, toml::to_string(&chosen).map_err(io::Error::other)?)
+    }
+
+    pub fn get(&self, device: &str) -> Option<&VolumeControls> {
+        self.devices.get(device)
+    }
+
+    pub fn get_mut(&mut self, device: &str) -> &mut VolumeControls {
+        self.devices.entry(device.to_owned()).or_default()
+    }
+}
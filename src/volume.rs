@@ -0,0 +1,159 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+};
+
+// A volume level as a linear amplitude, 1.0 being 100% (0 dBFS); backends like PulseAudio allow levels past it.
+// Commands and backends pass Volume, the GUI keeps whole percents as u8 for its widgets and converts at the commands.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Volume(f32);
+
+impl Volume {
+    pub const MUTE: Volume = Volume(0.0);
+    pub const FULL: Volume = Volume(1.0);
+    // Past this a reading is taken as garbage, PulseAudio's own volume control stops at 153%
+    pub const MAX_PERCENT: u16 = 1000;
+
+    // None past MAX_PERCENT
+    pub fn from_percent(percent: u16) -> Option<Self> {
+        (percent <= Self::MAX_PERCENT).then(|| Self(percent as f32 / 100.0))
+    }
+
+    // None for anything that isn't a finite, non-negative amplitude
+    pub fn from_linear(linear: f32) -> Option<Self> {
+        // abs() turns -0.0 into the same mute as 0.0
+        (linear.is_finite() && linear >= 0.0).then_some(Self(linear.abs()))
+    }
+
+    // Negative infinity is mute, None for NaN and levels no amplitude can reach
+    pub fn from_db(db: f32) -> Option<Self> {
+        if db == f32::NEG_INFINITY {
+            Some(Self::MUTE)
+        } else {
+            Self::from_linear(10f32.powf(db / 20.0))
+        }
+    }
+
+    // Rounded to the nearest percent, truncating would turn 0.29 into 28% since it is stored as 0.28999...
+    pub fn percent(self) -> u16 {
+        (self.0 * 100.0).round() as u16
+    }
+
+    // Same as `percent`, saturating at the 255% cpvc can report
+    pub fn percent_u8(self) -> u8 {
+        self.percent().min(u8::MAX as u16) as u8
+    }
+
+    pub fn linear(self) -> f32 {
+        self.0
+    }
+
+    // dBFS, negative infinity when muted
+    pub fn db(self) -> f32 {
+        20.0 * self.0.log10()
+    }
+
+    pub fn is_mute(self) -> bool {
+        self.percent() == 0
+    }
+
+    // Label for the GUI, e.g. "-6.0 dB"
+    pub fn db_label(self) -> String {
+        if self.is_mute() {
+            String::from("-∞ dB")
+        } else {
+            format!("{:.1} dB", self.db())
+        }
+    }
+}
+
+// Always in range, unlike from_percent
+impl From<u8> for Volume {
+    fn from(percent: u8) -> Self {
+        Self(percent as f32 / 100.0)
+    }
+}
+
+// Construction keeps the amplitude finite, so the total order is the numeric one
+impl PartialEq for Volume {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Volume {}
+
+impl PartialOrd for Volume {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Volume {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.percent())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_rounds_instead_of_truncating() {
+        assert_eq!(Volume::from_linear(0.29).unwrap().percent(), 29);
+        assert_eq!(Volume::from_linear(0.004).unwrap().percent(), 0);
+        assert_eq!(Volume::from_linear(0.005).unwrap().percent(), 1);
+        assert_eq!(Volume::from(42).percent(), 42);
+    }
+
+    #[test]
+    fn from_percent_checks_its_range() {
+        assert_eq!(Volume::from_percent(150).map(Volume::percent), Some(150));
+        assert_eq!(Volume::from_percent(Volume::MAX_PERCENT).map(Volume::percent), Some(Volume::MAX_PERCENT));
+        assert_eq!(Volume::from_percent(Volume::MAX_PERCENT + 1), None);
+    }
+
+    #[test]
+    fn percent_u8_saturates() {
+        assert_eq!(Volume::from_percent(300).unwrap().percent_u8(), u8::MAX);
+    }
+
+    #[test]
+    fn from_linear_rejects_invalid_amplitudes() {
+        assert_eq!(Volume::from_linear(f32::NAN), None);
+        assert_eq!(Volume::from_linear(f32::INFINITY), None);
+        assert_eq!(Volume::from_linear(-0.5), None);
+        assert_eq!(Volume::from_linear(-0.0), Some(Volume::MUTE));
+    }
+
+    #[test]
+    fn db_round_trips() {
+        assert_eq!(Volume::FULL.db(), 0.0);
+        assert_eq!(Volume::MUTE.db(), f32::NEG_INFINITY);
+        assert_eq!(Volume::from_db(f32::NEG_INFINITY), Some(Volume::MUTE));
+        assert_eq!(Volume::from_db(f32::NAN), None);
+        let half = Volume::from_db(-6.0206).unwrap();
+        assert_eq!(half.percent(), 50);
+        assert!((half.db() + 6.0206).abs() < 1e-3);
+    }
+
+    #[test]
+    fn db_label_shows_mute_as_infinity() {
+        assert_eq!(Volume::MUTE.db_label(), "-∞ dB");
+        assert_eq!(Volume::from(50).db_label(), "-6.0 dB");
+    }
+
+    #[test]
+    fn ordering_is_numeric() {
+        assert!(Volume::from(20) < Volume::from(21));
+        assert_eq!(Volume::from_linear(0.5), Some(Volume::from(50)));
+        assert_eq!(Volume::from(100), Volume::FULL);
+    }
+}